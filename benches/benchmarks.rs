@@ -76,46 +76,48 @@ fn day_07_part_2() {
 
 #[divan::bench]
 fn day_08_part_1() {
-    day_08::part1::main(divan::black_box(include_str!("../src/day_08/input.txt"))).unwrap();
+    day_08::part1(divan::black_box(include_str!("../src/day_08/input.txt"))).unwrap();
 }
 
 #[divan::bench]
 fn day_08_part_2() {
-    day_08::part2::main(divan::black_box(include_str!("../src/day_08/input.txt"))).unwrap();
+    day_08::part2(divan::black_box(include_str!("../src/day_08/input.txt"))).unwrap();
 }
-// #[divan::bench]
-// fn day_09_part_1() {
-//     day_09::part1::main(divan::black_box(include_str!("../src/day_09/input.txt"))).unwrap();
-// }
 
-// #[divan::bench]
-// fn day_09_part_2() {
-//     day_09::part2::main(divan::black_box(include_str!("../src/day_09/input.txt"))).unwrap();
-// }
+#[divan::bench]
+fn day_09_part_1() {
+    day_09::part1(divan::black_box(include_str!("../src/day_09/input.txt"))).unwrap();
+}
+
+#[divan::bench]
+fn day_09_part_2() {
+    day_09::part2(divan::black_box(include_str!("../src/day_09/input.txt"))).unwrap();
+}
 
 #[divan::bench]
 fn day_10_part_1() {
-    day_10::part1::main(divan::black_box(include_str!("../src/day_10/input.txt"))).unwrap();
+    day_10::part1(divan::black_box(include_str!("../src/day_10/input.txt"))).unwrap();
 }
 
 #[divan::bench]
 fn day_10_part_2() {
-    day_10::part2::main(divan::black_box(include_str!("../src/day_10/input.txt"))).unwrap();
+    day_10::part2(divan::black_box(include_str!("../src/day_10/input.txt"))).unwrap();
 }
 
 #[divan::bench]
 fn day_11_part_1() {
-    day_11::part1::main(divan::black_box(include_str!("../src/day_11/input.txt"))).unwrap();
+    day_11::part1(divan::black_box(include_str!("../src/day_11/input.txt"))).unwrap();
 }
 
 #[divan::bench]
-fn day_11_part_1_cache() {
-    day_11::part2::part1_cached(divan::black_box(include_str!("../src/day_11/input.txt"))).unwrap();
+fn day_11_part_2_big() {
+    let input = divan::black_box(include_str!("../src/day_11/input.txt"));
+    day_11::stones_after::<num_bigint::BigUint>(input, 75).unwrap();
 }
 
 #[divan::bench]
 fn day_11_part_2() {
-    day_11::part2::main(divan::black_box(include_str!("../src/day_11/input.txt"))).unwrap();
+    day_11::part2(divan::black_box(include_str!("../src/day_11/input.txt"))).unwrap();
 }
 
 #[divan::bench]
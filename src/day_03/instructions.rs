@@ -0,0 +1,265 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+pub struct State {
+    pub enabled: bool,
+    pub toggles: Vec<bool>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            enabled: true,
+            toggles: Vec::new(),
+        }
+    }
+}
+
+pub trait Instruction {
+    fn name(&self) -> &str;
+    fn arity(&self) -> usize;
+    fn execute(&self, args: &[i64], state: &mut State) -> i64;
+
+    // toggles have to run even when the machine is disabled, otherwise `do()` could never fire
+    fn runs_when_disabled(&self) -> bool {
+        false
+    }
+}
+
+pub struct Mul;
+pub struct Add;
+pub struct Do;
+pub struct Dont;
+
+impl Instruction for Mul {
+    fn name(&self) -> &str {
+        "mul"
+    }
+    fn arity(&self) -> usize {
+        2
+    }
+    fn execute(&self, args: &[i64], _state: &mut State) -> i64 {
+        args[0] * args[1]
+    }
+}
+
+impl Instruction for Add {
+    fn name(&self) -> &str {
+        "add"
+    }
+    fn arity(&self) -> usize {
+        2
+    }
+    fn execute(&self, args: &[i64], _state: &mut State) -> i64 {
+        args[0] + args[1]
+    }
+}
+
+impl Instruction for Do {
+    fn name(&self) -> &str {
+        "do"
+    }
+    fn arity(&self) -> usize {
+        0
+    }
+    fn execute(&self, _args: &[i64], state: &mut State) -> i64 {
+        state.enabled = true;
+        0
+    }
+    fn runs_when_disabled(&self) -> bool {
+        true
+    }
+}
+
+impl Instruction for Dont {
+    fn name(&self) -> &str {
+        "don't"
+    }
+    fn arity(&self) -> usize {
+        0
+    }
+    fn execute(&self, _args: &[i64], state: &mut State) -> i64 {
+        state.enabled = false;
+        0
+    }
+    fn runs_when_disabled(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub offset: usize,
+    pub text: String,
+    pub enabled: bool,
+    pub value: i64,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.enabled { "on " } else { "off" };
+        write!(
+            f,
+            "{:>6} [{}] {} = {}",
+            self.offset, state, self.text, self.value
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Engine {
+    instructions: Vec<Box<dyn Instruction>>,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    pub fn with_defaults() -> Engine {
+        Engine::new().register(Mul).register(Do).register(Dont)
+    }
+
+    pub fn register(mut self, instruction: impl Instruction + 'static) -> Engine {
+        self.instructions.push(Box::new(instruction));
+        self
+    }
+
+    fn compile(&self) -> Result<Regex> {
+        let alternatives = self
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| {
+                let args = vec![r"\d+"; instruction.arity()].join(",");
+                format!(
+                    r"(?P<i{index}>{}\({args}\))",
+                    regex::escape(instruction.name())
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("|");
+        Regex::new(&alternatives).context("failed to build instruction regex")
+    }
+
+    pub fn trace(&self, input: &str) -> Result<Vec<TraceEntry>> {
+        let re = self.compile()?;
+        let args_re = Regex::new(r"\d+").unwrap();
+        let mut state = State::default();
+        let mut trace = Vec::new();
+
+        for capture in re.captures_iter(input) {
+            let (index, found) = (0..self.instructions.len())
+                .find_map(|index| capture.name(&format!("i{index}")).map(|m| (index, m)))
+                .context("matched text does not belong to any instruction")?;
+            let instruction = &self.instructions[index];
+            // skip the name so digits inside it are never taken as arguments
+            let args = args_re
+                .find_iter(&found.as_str()[instruction.name().len()..])
+                .map(|arg| {
+                    arg.as_str()
+                        .parse::<i64>()
+                        .context(format!("failed to parse argument in {}", found.as_str()))
+                })
+                .collect::<Result<Vec<i64>>>()?;
+
+            let value = if state.enabled || instruction.runs_when_disabled() {
+                instruction.execute(&args, &mut state)
+            } else {
+                0
+            };
+            trace.push(TraceEntry {
+                offset: found.start(),
+                text: found.as_str().to_string(),
+                enabled: state.enabled,
+                value,
+            });
+        }
+        Ok(trace)
+    }
+
+    pub fn run(&self, input: &str) -> Result<i64> {
+        Ok(self.trace(input)?.iter().map(|entry| entry.value).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // push/pop style toggles: `off()` disables until the matching `on()`
+    struct Off;
+    struct On;
+
+    impl Instruction for Off {
+        fn name(&self) -> &str {
+            "off"
+        }
+        fn arity(&self) -> usize {
+            0
+        }
+        fn execute(&self, _args: &[i64], state: &mut State) -> i64 {
+            state.toggles.push(state.enabled);
+            state.enabled = false;
+            0
+        }
+        fn runs_when_disabled(&self) -> bool {
+            true
+        }
+    }
+
+    impl Instruction for On {
+        fn name(&self) -> &str {
+            "on"
+        }
+        fn arity(&self) -> usize {
+            0
+        }
+        fn execute(&self, _args: &[i64], state: &mut State) -> i64 {
+            state.enabled = state.toggles.pop().unwrap_or(true);
+            0
+        }
+        fn runs_when_disabled(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let result = Engine::with_defaults().run(input);
+        assert_eq!(result.unwrap(), 48);
+    }
+
+    #[test]
+    fn test_add() {
+        let input = "add(1,2)mul(3,4)don't()add(100,100)do()add(5,5)";
+        let result = Engine::with_defaults().register(Add).run(input);
+        assert_eq!(result.unwrap(), 3 + 12 + 10);
+    }
+
+    #[test]
+    fn test_nested_toggles() {
+        let input = "mul(1,1)off()mul(2,2)off()mul(3,3)on()mul(4,4)on()mul(5,5)";
+        let engine = Engine::new().register(Mul).register(Off).register(On);
+        assert_eq!(engine.run(input).unwrap(), 1 + 25);
+    }
+
+    #[test]
+    fn test_trace() {
+        let input = "mul(2,4)don't()mul(5,5)do()";
+        let trace = Engine::with_defaults().trace(input).unwrap();
+        let rendered: Vec<String> = trace.iter().map(|entry| entry.to_string()).collect();
+        assert_eq!(
+            rendered,
+            [
+                "     0 [on ] mul(2,4) = 8",
+                "     8 [off] don't() = 0",
+                "    15 [off] mul(5,5) = 0",
+                "    23 [on ] do() = 0",
+            ]
+        );
+    }
+}
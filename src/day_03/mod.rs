@@ -1,60 +1,18 @@
-use regex::{Match, Regex};
+pub mod instructions;
+use instructions::{Engine, Mul};
 
 pub fn part1(input: &str) -> i64 {
-    let re: Regex = Regex::new(r"mul\((\d+),(\d+)\)").unwrap();
-
-    re.captures_iter(input)
-        .map(|c| (&c).extract())
-        .map(|(_, [mul_left, mul_right])| {
-            mul_left.parse::<i64>().unwrap() * mul_right.parse::<i64>().unwrap()
-        })
-        .sum()
+    Engine::new().register(Mul).run(input).unwrap()
 }
 
 pub fn part2(input: &str) -> i64 {
-    let re: Regex = Regex::new(r"(mul\((\d+),(\d+)\)|do\(\)|don't\(\))").unwrap();
-
-    let mut do_we_do = true;
-    let mut sum = 0;
-    for capture in re.captures_iter(input) {
-        if let Some(x) = capture.get(0) {
-            let matched_str = x.as_str();
-            let result = match matched_str {
-                "do()" => {
-                    do_we_do = true;
-                    0
-                }
-                "don't()" => {
-                    do_we_do = false;
-                    0
-                }
-                _ => {
-                    if do_we_do {
-                        let (mul_left, mul_right) = (
-                            get_i64_value(capture.get(2)).unwrap(),
-                            get_i64_value(capture.get(3)).unwrap(),
-                        );
-                        mul_left * mul_right
-                    } else {
-                        0
-                    }
-                }
-            };
-            sum = sum + result;
-        } else {
-            panic!(":(")
-        }
-    }
-    sum
-}
-
-fn get_i64_value(asd: Option<Match>) -> Option<i64> {
-    asd?.as_str().parse::<i64>().ok()
+    Engine::with_defaults().run(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use regex::Regex;
     #[test]
 
     fn test_re() {
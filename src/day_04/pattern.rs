@@ -0,0 +1,175 @@
+use anyhow::{bail, Result};
+
+use super::{CoordinatePair, WordSearch};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    Rotations,
+    RotationsAndReflections,
+}
+
+// a small grid where `None` is a wildcard that matches anything
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    cells: Vec<Vec<Option<char>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    pub anchor: CoordinatePair,
+    pub variant: Pattern,
+    pub cells: Vec<CoordinatePair>,
+}
+
+impl Pattern {
+    // rows are separated by newlines or `/`, `.` is the wildcard, e.g. "M.S / .A. / M.S"
+    pub fn parse(input: &str) -> Result<Pattern> {
+        let cells: Vec<Vec<Option<char>>> = input
+            .split(['\n', '/'])
+            .map(|row| row.trim())
+            .filter(|row| !row.is_empty())
+            .map(|row| {
+                row.chars()
+                    .map(|c| if c == '.' { None } else { Some(c) })
+                    .collect()
+            })
+            .collect();
+        if cells.is_empty() {
+            bail!("pattern is empty");
+        }
+        if cells.iter().any(|row| row.len() != cells[0].len()) {
+            bail!("pattern rows have different lengths: {input}");
+        }
+        Ok(Pattern { cells })
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    fn rotate(&self) -> Pattern {
+        let cells = (0..self.width())
+            .map(|x| (0..self.height()).rev().map(|y| self.cells[y][x]).collect())
+            .collect();
+        Pattern { cells }
+    }
+
+    fn reflect(&self) -> Pattern {
+        let cells = self
+            .cells
+            .iter()
+            .map(|row| row.iter().rev().cloned().collect())
+            .collect();
+        Pattern { cells }
+    }
+
+    // distinct variants only, otherwise a symmetric pattern would be reported several times per anchor
+    pub fn variants(&self, symmetry: Symmetry) -> Vec<Pattern> {
+        let mut bases = vec![self.clone()];
+        if symmetry == Symmetry::RotationsAndReflections {
+            bases.push(self.reflect());
+        }
+        let mut variants: Vec<Pattern> = Vec::new();
+        for base in bases {
+            let mut current = base;
+            let turns = if symmetry == Symmetry::None { 1 } else { 4 };
+            for _ in 0..turns {
+                let next = current.rotate();
+                if !variants.contains(&current) {
+                    variants.push(current);
+                }
+                current = next;
+            }
+        }
+        variants
+    }
+
+    fn matches_at(&self, puzzle: &WordSearch, x: usize, y: usize) -> Option<Vec<CoordinatePair>> {
+        let mut cells = Vec::new();
+        for (dy, row) in self.cells.iter().enumerate() {
+            for (dx, expected) in row.iter().enumerate() {
+                let Some(expected) = expected else {
+                    continue;
+                };
+                let found = puzzle.get(y + dy).and_then(|line| line.get(x + dx))?;
+                if found != expected {
+                    return None;
+                }
+                cells.push(CoordinatePair((x + dx) as i64, (y + dy) as i64));
+            }
+        }
+        Some(cells)
+    }
+}
+
+pub fn find_pattern(
+    puzzle: &WordSearch,
+    pattern: &Pattern,
+    symmetry: Symmetry,
+) -> Vec<PatternMatch> {
+    let variants = pattern.variants(symmetry);
+    let mut matches = Vec::new();
+    for (y, row) in puzzle.iter().enumerate() {
+        for x in 0..row.len() {
+            for variant in &variants {
+                if let Some(cells) = variant.matches_at(puzzle, x, y) {
+                    matches.push(PatternMatch {
+                        anchor: CoordinatePair(x as i64, y as i64),
+                        variant: variant.clone(),
+                        cells,
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_wordsearch;
+    use super::*;
+
+    #[test]
+    fn test_variants() {
+        let x_mas = Pattern::parse("M.S / .A. / M.S").unwrap();
+        assert_eq!(x_mas.variants(Symmetry::None).len(), 1);
+        assert_eq!(x_mas.variants(Symmetry::Rotations).len(), 4);
+        assert_eq!(x_mas.variants(Symmetry::RotationsAndReflections).len(), 4);
+
+        let corner = Pattern::parse("AB\nC.").unwrap();
+        assert_eq!(corner.variants(Symmetry::RotationsAndReflections).len(), 8);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let pattern = Pattern::parse("AB./..C").unwrap();
+        assert_eq!(pattern.rotate(), Pattern::parse(".A/.B/C.").unwrap());
+    }
+
+    #[test]
+    fn test_x_mas() {
+        let puzzle = parse_wordsearch("M.M\n.A.\nS.S\n.A.\nM.M");
+        let pattern = Pattern::parse("M.S/.A./M.S").unwrap();
+        let result = find_pattern(&puzzle, &pattern, Symmetry::Rotations);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].anchor, CoordinatePair(0, 0));
+        assert_eq!(result[1].anchor, CoordinatePair(0, 2));
+        assert_eq!(result[0].variant, Pattern::parse("M.M/.A./S.S").unwrap());
+        assert_eq!(result[1].variant, Pattern::parse("S.S/.A./M.M").unwrap());
+    }
+
+    #[test]
+    fn test_plus() {
+        let puzzle = parse_wordsearch(".M.\nMAS\n.S.\n...");
+        let pattern = Pattern::parse(".M./MAS/.S.").unwrap();
+        let result = find_pattern(&puzzle, &pattern, Symmetry::None);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].cells.len(), 5);
+    }
+}
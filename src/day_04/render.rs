@@ -0,0 +1,164 @@
+use std::{fs::File, io, path::Path};
+
+use super::{pattern::PatternMatch, search::WordMatch, CoordinatePair, WordSearch};
+
+pub trait Highlight {
+    fn cells(&self) -> &[CoordinatePair];
+}
+
+impl Highlight for WordMatch {
+    fn cells(&self) -> &[CoordinatePair] {
+        &self.cells
+    }
+}
+
+impl Highlight for PatternMatch {
+    fn cells(&self) -> &[CoordinatePair] {
+        &self.cells
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    // unmatched cells become `.`, same as the puzzle description
+    Plain,
+    // whole grid is kept, every match gets its own ANSI colour
+    Coloured,
+}
+
+const PALETTE: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+pub fn render<H: Highlight>(puzzle: &WordSearch, matches: &[H], style: RenderStyle) -> String {
+    // later matches win when cells overlap
+    let mut owner: Vec<Vec<Option<usize>>> =
+        puzzle.iter().map(|row| vec![None; row.len()]).collect();
+    for (index, found) in matches.iter().enumerate() {
+        for cell in found.cells() {
+            if let Some(slot) = owner
+                .get_mut(cell.1 as usize)
+                .and_then(|row| row.get_mut(cell.0 as usize))
+            {
+                *slot = Some(index);
+            }
+        }
+    }
+
+    puzzle
+        .iter()
+        .zip(owner)
+        .map(|(row, owners)| {
+            row.iter()
+                .zip(owners)
+                .map(|(character, owner)| match (style, owner) {
+                    (RenderStyle::Plain, Some(_)) => character.to_string(),
+                    (RenderStyle::Plain, None) => ".".to_string(),
+                    (RenderStyle::Coloured, Some(index)) => {
+                        format!(
+                            "\x1b[{}m{}\x1b[0m",
+                            PALETTE[index % PALETTE.len()],
+                            character
+                        )
+                    }
+                    (RenderStyle::Coloured, None) => character.to_string(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn render_to<H: Highlight>(
+    out: &mut impl io::Write,
+    puzzle: &WordSearch,
+    matches: &[H],
+    style: RenderStyle,
+) -> io::Result<()> {
+    writeln!(out, "{}", render(puzzle, matches, style))
+}
+
+pub fn render_to_file<H: Highlight>(
+    path: impl AsRef<Path>,
+    puzzle: &WordSearch,
+    matches: &[H],
+    style: RenderStyle,
+) -> io::Result<()> {
+    render_to(&mut File::create(path)?, puzzle, matches, style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        parse_wordsearch,
+        pattern::{find_pattern, Pattern, Symmetry},
+        search::{find_words, SearchOptions},
+    };
+    use super::*;
+
+    const EXAMPLE: &str = r#"MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX"#;
+
+    #[test]
+    fn test_render_xmas() {
+        let puzzle = parse_wordsearch(EXAMPLE);
+        let matches = find_words(&puzzle, &["XMAS"], &SearchOptions::all()).unwrap();
+        let expected = r#"....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX"#;
+        assert_eq!(render(&puzzle, &matches, RenderStyle::Plain), expected);
+    }
+
+    #[test]
+    fn test_render_x_mas() {
+        let puzzle = parse_wordsearch(EXAMPLE);
+        let pattern = Pattern::parse("M.S/.A./M.S").unwrap();
+        let matches = find_pattern(&puzzle, &pattern, Symmetry::Rotations);
+        let expected = r#".M.S......
+..A..MSMS.
+.M.S.MAA..
+..A.ASMSM.
+.M.S.M....
+..........
+S.S.S.S.S.
+.A.A.A.A..
+M.M.M.M.M.
+.........."#;
+        assert_eq!(render(&puzzle, &matches, RenderStyle::Plain), expected);
+    }
+
+    #[test]
+    fn test_render_coloured() {
+        let puzzle = parse_wordsearch("XMAS.\nSAMX.");
+        let matches = find_words(&puzzle, &["XMAS"], &SearchOptions::orthogonal()).unwrap();
+        let rendered = render(&puzzle, &matches, RenderStyle::Coloured);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(
+            lines[0],
+            "\x1b[31mX\x1b[0m\x1b[31mM\x1b[0m\x1b[31mA\x1b[0m\x1b[31mS\x1b[0m."
+        );
+        assert!(lines[1].starts_with("\x1b[32mS\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_to() {
+        let puzzle = parse_wordsearch("XMAS\n....");
+        let matches = find_words(&puzzle, &["XMAS"], &SearchOptions::all()).unwrap();
+        let mut out = Vec::new();
+        render_to(&mut out, &puzzle, &matches, RenderStyle::Plain).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "XMAS\n....\n");
+    }
+}
@@ -0,0 +1,409 @@
+use anyhow::{ensure, Result};
+use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
+
+use super::{CoordinatePair, WordSearch};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ORTHOGONAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+    pub const DIAGONAL: [Direction; 4] = [
+        Direction::NorthEast,
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::NorthWest,
+    ];
+
+    // y grows downwards, same as the row index of the puzzle
+    pub fn delta(&self) -> CoordinatePair {
+        match self {
+            Direction::North => CoordinatePair(0, -1),
+            Direction::NorthEast => CoordinatePair(1, -1),
+            Direction::East => CoordinatePair(1, 0),
+            Direction::SouthEast => CoordinatePair(1, 1),
+            Direction::South => CoordinatePair(0, 1),
+            Direction::SouthWest => CoordinatePair(-1, 1),
+            Direction::West => CoordinatePair(-1, 0),
+            Direction::NorthWest => CoordinatePair(-1, -1),
+        }
+    }
+}
+
+pub struct SearchOptions {
+    pub directions: Vec<Direction>,
+    pub wrap: bool,
+}
+
+impl SearchOptions {
+    pub fn orthogonal() -> SearchOptions {
+        SearchOptions {
+            directions: Direction::ORTHOGONAL.to_vec(),
+            wrap: false,
+        }
+    }
+
+    pub fn diagonal() -> SearchOptions {
+        SearchOptions {
+            directions: Direction::DIAGONAL.to_vec(),
+            wrap: false,
+        }
+    }
+
+    pub fn all() -> SearchOptions {
+        SearchOptions {
+            directions: [Direction::ORTHOGONAL, Direction::DIAGONAL].concat(),
+            wrap: false,
+        }
+    }
+
+    pub fn wrapping(mut self) -> SearchOptions {
+        self.wrap = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordMatch {
+    pub word: usize,
+    pub start: CoordinatePair,
+    pub direction: Direction,
+    pub cells: Vec<CoordinatePair>,
+}
+
+#[derive(Default)]
+struct Node {
+    next: FxHashMap<char, usize>,
+    fail: usize,
+    // indices of every word ending here, including the ones reached through `fail`
+    outputs: Vec<usize>,
+}
+
+// Aho-Corasick automaton, so every line is scanned once no matter how many words we look for
+struct Automaton {
+    nodes: Vec<Node>,
+    lengths: Vec<usize>,
+}
+
+impl Automaton {
+    fn new(words: &[&str]) -> Automaton {
+        let mut nodes = vec![Node::default()];
+        let mut lengths = Vec::with_capacity(words.len());
+
+        for (index, word) in words.iter().enumerate() {
+            let mut state = 0;
+            for character in word.chars() {
+                state = match nodes[state].next.get(&character) {
+                    Some(next) => *next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[state].next.insert(character, next);
+                        next
+                    }
+                };
+            }
+            nodes[state].outputs.push(index);
+            lengths.push(word.chars().count());
+        }
+
+        let mut queue: VecDeque<usize> = nodes[0].next.values().cloned().collect();
+        while let Some(state) = queue.pop_front() {
+            let edges: Vec<(char, usize)> =
+                nodes[state].next.iter().map(|(c, n)| (*c, *n)).collect();
+            for (character, next) in edges {
+                let mut fallback = nodes[state].fail;
+                while fallback != 0 && !nodes[fallback].next.contains_key(&character) {
+                    fallback = nodes[fallback].fail;
+                }
+                let fail = match nodes[fallback].next.get(&character) {
+                    Some(target) if *target != next => *target,
+                    _ => 0,
+                };
+                nodes[next].fail = fail;
+                let inherited = nodes[fail].outputs.clone();
+                nodes[next].outputs.extend(inherited);
+                queue.push_back(next);
+            }
+        }
+
+        Automaton { nodes, lengths }
+    }
+
+    fn step(&self, mut state: usize, character: char) -> usize {
+        loop {
+            if let Some(next) = self.nodes[state].next.get(&character) {
+                return *next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+
+    fn max_len(&self) -> usize {
+        self.lengths.iter().cloned().max().unwrap_or(0)
+    }
+}
+
+fn get_char(puzzle: &WordSearch, coord: &CoordinatePair) -> Option<char> {
+    if coord.0 < 0 || coord.1 < 0 {
+        return None;
+    }
+    puzzle.get(coord.1 as usize)?.get(coord.0 as usize).cloned()
+}
+
+// every maximal run of cells along `direction`, a ragged row simply ends its lines early
+fn straight_lines(puzzle: &WordSearch, direction: &Direction) -> Vec<Vec<CoordinatePair>> {
+    let delta = direction.delta();
+    let back = CoordinatePair(-delta.0, -delta.1);
+    let mut lines = Vec::new();
+    for (y, row) in puzzle.iter().enumerate() {
+        for x in 0..row.len() {
+            let start = CoordinatePair(x as i64, y as i64);
+            if get_char(puzzle, &start.add(&back)).is_some() {
+                continue;
+            }
+            let mut line = Vec::new();
+            let mut cursor = start;
+            while get_char(puzzle, &cursor).is_some() {
+                let next = cursor.add(&delta);
+                line.push(cursor);
+                cursor = next;
+            }
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+// on a torus every cell belongs to exactly one cycle per direction
+fn wrapped_cycles(puzzle: &WordSearch, direction: &Direction) -> Vec<Vec<CoordinatePair>> {
+    let height = puzzle.len() as i64;
+    let width = puzzle.first().map_or(0, |row| row.len()) as i64;
+    let delta = direction.delta();
+    let mut visited = vec![false; (width * height) as usize];
+    let mut cycles = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if visited[(y * width + x) as usize] {
+                continue;
+            }
+            let mut cycle = Vec::new();
+            let mut cursor = CoordinatePair(x, y);
+            while !visited[(cursor.1 * width + cursor.0) as usize] {
+                visited[(cursor.1 * width + cursor.0) as usize] = true;
+                let next = CoordinatePair(
+                    (cursor.0 + delta.0).rem_euclid(width),
+                    (cursor.1 + delta.1).rem_euclid(height),
+                );
+                cycle.push(cursor);
+                cursor = next;
+            }
+            cycles.push(cycle);
+        }
+    }
+    cycles
+}
+
+pub fn find_words(
+    puzzle: &WordSearch,
+    words: &[&str],
+    options: &SearchOptions,
+) -> Result<Vec<WordMatch>> {
+    ensure!(
+        words.iter().all(|word| !word.is_empty()),
+        "can't search for an empty word"
+    );
+    // a torus needs every row the same length
+    if options.wrap {
+        let width = puzzle.first().map_or(0, |row| row.len());
+        ensure!(
+            puzzle.iter().all(|row| row.len() == width),
+            "can't wrap around a grid with rows of different lengths"
+        );
+    }
+    let automaton = Automaton::new(words);
+    let overlap = automaton.max_len().saturating_sub(1);
+    let mut matches = Vec::new();
+
+    for direction in &options.directions {
+        let lines = if options.wrap {
+            wrapped_cycles(puzzle, direction)
+        } else {
+            straight_lines(puzzle, direction)
+        };
+        for line in lines {
+            // cycles are walked a bit past their end so words crossing the seam are found too
+            let walk_len = if options.wrap {
+                line.len() + overlap
+            } else {
+                line.len()
+            };
+            let mut state = 0;
+            for position in 0..walk_len {
+                let cell = &line[position % line.len()];
+                state = automaton.step(state, get_char(puzzle, cell).unwrap());
+                for word in &automaton.nodes[state].outputs {
+                    let len = automaton.lengths[*word];
+                    let start = position + 1 - len;
+                    // a word longer than the cycle would use some of its cells twice
+                    if start >= line.len() || len > line.len() {
+                        continue;
+                    }
+                    let cells: Vec<CoordinatePair> = (start..=position)
+                        .map(|index| line[index % line.len()])
+                        .collect();
+                    matches.push(WordMatch {
+                        word: *word,
+                        start: cells[0],
+                        direction: *direction,
+                        cells,
+                    });
+                }
+            }
+        }
+    }
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_wordsearch;
+    use super::*;
+
+    const EXAMPLE: &str = r#"MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX"#;
+
+    #[test]
+    fn test_xmas() {
+        let puzzle = parse_wordsearch(EXAMPLE);
+        let result = find_words(&puzzle, &["XMAS"], &SearchOptions::all()).unwrap();
+        assert_eq!(result.len(), 18);
+    }
+
+    #[test]
+    fn test_match_position() {
+        let puzzle = parse_wordsearch("..XMAS\n......\nSAMX..");
+        let result = find_words(&puzzle, &["XMAS"], &SearchOptions::all()).unwrap();
+        assert_eq!(
+            result,
+            [
+                WordMatch {
+                    word: 0,
+                    start: CoordinatePair(2, 0),
+                    direction: Direction::East,
+                    cells: vec![
+                        CoordinatePair(2, 0),
+                        CoordinatePair(3, 0),
+                        CoordinatePair(4, 0),
+                        CoordinatePair(5, 0)
+                    ],
+                },
+                WordMatch {
+                    word: 0,
+                    start: CoordinatePair(3, 2),
+                    direction: Direction::West,
+                    cells: vec![
+                        CoordinatePair(3, 2),
+                        CoordinatePair(2, 2),
+                        CoordinatePair(1, 2),
+                        CoordinatePair(0, 2)
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_many_words() {
+        let puzzle = parse_wordsearch("SHEHERS\nXXXXXXX\nHISXXXX");
+        let words = ["HE", "SHE", "HIS", "HERS"];
+        let options = SearchOptions {
+            directions: vec![Direction::East],
+            wrap: false,
+        };
+        let result = find_words(&puzzle, &words, &options).unwrap();
+        let mut found: Vec<(&str, i64, i64)> = result
+            .iter()
+            .map(|m| (words[m.word], m.start.0, m.start.1))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                ("HE", 1, 0),
+                ("HE", 3, 0),
+                ("HERS", 3, 0),
+                ("HIS", 0, 2),
+                ("SHE", 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let puzzle = parse_wordsearch("ASXM\n....");
+        let plain = find_words(&puzzle, &["XMAS"], &SearchOptions::orthogonal()).unwrap();
+        assert_eq!(plain.len(), 0);
+        let wrapped =
+            find_words(&puzzle, &["XMAS"], &SearchOptions::orthogonal().wrapping()).unwrap();
+        assert_eq!(wrapped.len(), 1);
+        assert_eq!(wrapped[0].start, CoordinatePair(2, 0));
+        assert_eq!(wrapped[0].cells[3], CoordinatePair(1, 0));
+    }
+
+    #[test]
+    fn test_wrapping_longer_than_cycle() {
+        let puzzle = parse_wordsearch("XMAS\nXMAS");
+        let options = SearchOptions::orthogonal().wrapping();
+        let result = find_words(&puzzle, &["XMASX", "XMAS", "XXX"], &options).unwrap();
+        // only XMAS across both rows, XXX would go down the first column twice over
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|found| found.word == 1));
+    }
+
+    #[test]
+    fn test_empty_word() {
+        let puzzle = parse_wordsearch("XMAS");
+        assert!(find_words(&puzzle, &["XMAS", ""], &SearchOptions::all()).is_err());
+    }
+
+    #[test]
+    fn test_wrapping_ragged() {
+        let puzzle = parse_wordsearch("XMAS\nM\nA\nSAMX");
+        let options = SearchOptions::all().wrapping();
+        assert!(find_words(&puzzle, &["XMAS"], &options).is_err());
+    }
+
+    #[test]
+    fn test_ragged() {
+        let puzzle = parse_wordsearch("XMAS\nM\nA\nSAMX");
+        let result = find_words(&puzzle, &["XMAS"], &SearchOptions::all()).unwrap();
+        assert_eq!(result.len(), 3);
+    }
+}
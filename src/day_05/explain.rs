@@ -0,0 +1,355 @@
+use std::fmt;
+
+use anyhow::{bail, Result};
+
+use super::{graph::PrecedenceGraph, Predicate, SleighUpdates};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub predicate: Predicate,
+    pub before_position: usize,
+    pub after_position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub page: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub update: SleighUpdates,
+    pub violations: Vec<Violation>,
+    pub moves: Vec<Move>,
+    pub fixed: SleighUpdates,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |pages: &[usize]| {
+            pages
+                .iter()
+                .map(|page| page.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        writeln!(f, "{}", join(&self.update))?;
+        for violation in &self.violations {
+            writeln!(
+                f,
+                "  broken rule {}|{}: {} at position {}, {} at position {}",
+                violation.predicate.before,
+                violation.predicate.after,
+                violation.predicate.before,
+                violation.before_position,
+                violation.predicate.after,
+                violation.after_position
+            )?;
+        }
+        for step in &self.moves {
+            writeln!(
+                f,
+                "  move {} from position {} to {}",
+                step.page, step.from, step.to
+            )?;
+        }
+        writeln!(f, "  fixed: {}", join(&self.fixed))
+    }
+}
+
+// rule pairs that appear in the wrong order, i.e. `after` is printed before `before`
+pub fn violations(graph: &PrecedenceGraph, update: &[usize]) -> Vec<Violation> {
+    let mut found = Vec::new();
+    for early in 0..update.len() {
+        for late in (early + 1)..update.len() {
+            if graph.must_precede(update[late], update[early]) {
+                found.push(Violation {
+                    predicate: Predicate {
+                        before: update[late],
+                        after: update[early],
+                    },
+                    before_position: late,
+                    after_position: early,
+                });
+            }
+        }
+    }
+    found
+}
+
+fn bit(position: usize) -> u128 {
+    1 << position
+}
+
+// Smallest set of positions touching every conflicting pair. A later position conflicts with
+// an earlier one it has to go before, and that relation is transitive, so the pages that can
+// stay form an antichain of it. By Dilworth and König the largest antichain is read off a
+// maximum matching between later and earlier positions, which takes polynomial time.
+fn min_cover(conflicts: &[u128]) -> u128 {
+    fn augment(
+        late: usize,
+        earlier: &[u128],
+        matched: &mut [Option<usize>],
+        visited: &mut u128,
+    ) -> bool {
+        for early in 0..late {
+            if earlier[late] & bit(early) != 0 && *visited & bit(early) == 0 {
+                *visited |= bit(early);
+                if matched[early].is_none_or(|other| augment(other, earlier, matched, visited)) {
+                    matched[early] = Some(late);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    let len = conflicts.len();
+    let earlier: Vec<u128> = (0..len)
+        .map(|late| conflicts[late] & (bit(late) - 1))
+        .collect();
+    let mut matched: Vec<Option<usize>> = vec![None; len];
+    let mut unmatched = Vec::new();
+    for late in 0..len {
+        if !augment(late, &earlier, &mut matched, &mut 0) {
+            unmatched.push(late);
+        }
+    }
+
+    // alternating paths from unmatched later positions, the cover is everything reached on the
+    // earlier side and not reached on the later side
+    let (mut reached_late, mut reached_early) = (0_u128, 0_u128);
+    while let Some(late) = unmatched.pop() {
+        if reached_late & bit(late) != 0 {
+            continue;
+        }
+        reached_late |= bit(late);
+        for (early, partner) in matched.iter().enumerate().take(late) {
+            if earlier[late] & bit(early) != 0 && reached_early & bit(early) == 0 {
+                reached_early |= bit(early);
+                unmatched.extend(*partner);
+            }
+        }
+    }
+    (0..len)
+        .filter(|position| {
+            reached_late & bit(*position) == 0 || reached_early & bit(*position) != 0
+        })
+        .fold(0, |mask, position| mask | bit(position))
+}
+
+// The pages left in place must not conflict even through other pages (a before b before c),
+// so conflicts come from the transitive closure of the rules within the update. Moving the
+// minimum vertex cover of those conflicts is the least number of moves that fixes the update.
+pub fn explain(graph: &PrecedenceGraph, update: &[usize]) -> Result<Explanation> {
+    if update.len() > 128 {
+        bail!("update with {} pages is too long to explain", update.len());
+    }
+    graph.order(update)?;
+
+    let len = update.len();
+    let mut reach: Vec<u128> = (0..len)
+        .map(|from| {
+            (0..len)
+                .filter(|to| *to != from && graph.must_precede(update[from], update[*to]))
+                .fold(0, |mask, to| mask | bit(to))
+        })
+        .collect();
+    for middle in 0..len {
+        for from in 0..len {
+            if reach[from] & bit(middle) != 0 {
+                reach[from] |= reach[middle];
+            }
+        }
+    }
+
+    let mut conflicts = vec![0_u128; len];
+    for early in 0..len {
+        for late in (early + 1)..len {
+            if reach[late] & bit(early) != 0 {
+                conflicts[early] |= bit(late);
+                conflicts[late] |= bit(early);
+            }
+        }
+    }
+    let moved = min_cover(&conflicts);
+
+    // pages that stay keep their relative order, everything else follows the rules
+    let mut predecessors: Vec<u128> = (0..len)
+        .map(|to| {
+            (0..len)
+                .filter(|from| reach[*from] & bit(to) != 0)
+                .fold(0, |mask, from| mask | bit(from))
+        })
+        .collect();
+    let kept: Vec<usize> = (0..len)
+        .filter(|position| moved & bit(*position) == 0)
+        .collect();
+    for pair in kept.windows(2) {
+        predecessors[pair[1]] |= bit(pair[0]);
+    }
+    let mut placed = 0_u128;
+    let mut order = Vec::with_capacity(len);
+    while order.len() < len {
+        let next = (0..len)
+            .find(|position| placed & bit(*position) == 0 && predecessors[*position] & !placed == 0)
+            .unwrap();
+        placed |= bit(next);
+        order.push(next);
+    }
+
+    let moves = (0..len)
+        .filter(|position| moved & bit(*position) != 0)
+        .map(|position| Move {
+            page: update[position],
+            from: position,
+            to: order.iter().position(|p| *p == position).unwrap(),
+        })
+        .collect();
+
+    Ok(Explanation {
+        update: update.to_vec(),
+        violations: violations(graph, update),
+        moves,
+        fixed: order.iter().map(|position| update[*position]).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_input;
+    use super::*;
+
+    const EXAMPLE: &str = r#"47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47"#;
+
+    #[test]
+    fn test_violations() {
+        let (predicates, updates) = parse_input(EXAMPLE).unwrap();
+        let graph = PrecedenceGraph::new(&predicates);
+        assert!(violations(&graph, &updates[0]).is_empty());
+        assert_eq!(
+            violations(&graph, &updates[3]),
+            [Violation {
+                predicate: Predicate {
+                    before: 97,
+                    after: 75
+                },
+                before_position: 1,
+                after_position: 0,
+            }]
+        );
+        assert_eq!(violations(&graph, &updates[5]).len(), 4);
+    }
+
+    #[test]
+    fn test_moves() {
+        let (predicates, updates) = parse_input(EXAMPLE).unwrap();
+        let graph = PrecedenceGraph::new(&predicates);
+
+        let explanation = explain(&graph, &updates[3]).unwrap();
+        assert_eq!(explanation.moves.len(), 1);
+        assert_eq!(explanation.fixed, [97, 75, 47, 61, 53]);
+
+        let explanation = explain(&graph, &updates[4]).unwrap();
+        assert_eq!(explanation.moves.len(), 1);
+        assert_eq!(explanation.fixed, [61, 29, 13]);
+
+        let explanation = explain(&graph, &updates[5]).unwrap();
+        assert_eq!(
+            explanation.moves,
+            [
+                Move {
+                    page: 13,
+                    from: 1,
+                    to: 4
+                },
+                Move {
+                    page: 47,
+                    from: 4,
+                    to: 2
+                },
+            ]
+        );
+        assert_eq!(explanation.fixed, [97, 75, 47, 29, 13]);
+
+        assert!(explain(&graph, &updates[0]).unwrap().moves.is_empty());
+    }
+
+    #[test]
+    fn test_transitive_conflict() {
+        // only 2|1 is broken, but moving 2 alone cannot work since 3 has to go before 1 through 2
+        let (predicates, _) = parse_input("3|2\n2|1\n\n1").unwrap();
+        let graph = PrecedenceGraph::new(&predicates);
+        let explanation = explain(&graph, &[1, 3, 2]).unwrap();
+        assert_eq!(
+            explanation.moves,
+            [Move {
+                page: 1,
+                from: 0,
+                to: 2
+            }]
+        );
+        assert_eq!(explanation.fixed, [3, 2, 1]);
+    }
+
+    #[test]
+    fn test_long_update() {
+        // odd pages in order, even pages in order, but every even page has to go first
+        let rules: Vec<String> = (0..64)
+            .flat_map(|even| (0..64).map(move |odd| format!("{}|{}", 2 * even, 2 * odd + 1)))
+            .collect();
+        let (predicates, _) = parse_input(&format!("{}\n\n1", rules.join("\n"))).unwrap();
+        let graph = PrecedenceGraph::new(&predicates);
+        let update: Vec<usize> = (0..64)
+            .map(|odd| 2 * odd + 1)
+            .chain((0..64).map(|even| 2 * even))
+            .collect();
+        let explanation = explain(&graph, &update).unwrap();
+        assert_eq!(explanation.moves.len(), 64);
+        assert!(violations(&graph, &explanation.fixed).is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        let (predicates, updates) = parse_input(EXAMPLE).unwrap();
+        let graph = PrecedenceGraph::new(&predicates);
+        let explanation = explain(&graph, &updates[3]).unwrap();
+        assert_eq!(
+            explanation.to_string(),
+            "75,97,47,61,53
+  broken rule 97|75: 97 at position 1, 75 at position 0
+  move 97 from position 1 to 0
+  fixed: 97,75,47,61,53
+"
+        );
+    }
+}
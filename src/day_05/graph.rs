@@ -0,0 +1,460 @@
+use std::{cmp::Reverse, collections::BinaryHeap, fmt};
+
+use anyhow::{bail, Context, Result};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::{Predicate, SleighUpdates};
+
+// what to do with two pages that no rule puts in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Undefined {
+    // keep them in the order they had in the update
+    Stable,
+    // fail, the rules have to decide every pair
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplicates {
+    // every copy is ordered on its own
+    Keep,
+    // only the first copy of a page stays in the update
+    Merge,
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderOptions {
+    pub undefined: Undefined,
+    pub duplicates: Duplicates,
+}
+
+impl Default for OrderOptions {
+    fn default() -> Self {
+        OrderOptions {
+            undefined: Undefined::Stable,
+            duplicates: Duplicates::Keep,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CycleError {
+    pub cycle: Vec<usize>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pages: Vec<String> = self
+            .cycle
+            .iter()
+            .chain(self.cycle.first())
+            .map(|page| page.to_string())
+            .collect();
+        write!(f, "ordering rules contain a cycle: {}", pages.join(" -> "))
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+// longest update whose orders can be counted or enumerated
+pub const MAX_PAGES: usize = 128;
+// counting remembers every set of pages that can come first, few rules make that a lot of sets
+const MAX_SUBSETS: usize = 1 << 16;
+// most orders `enumerate_orders` lists before giving up
+pub const MAX_ORDERS: usize = 100_000;
+
+// rules compiled once into a bitset adjacency matrix, so checking a pair is O(1)
+pub struct PrecedenceGraph {
+    index: FxHashMap<usize, usize>,
+    before: Vec<Vec<u64>>,
+}
+
+impl PrecedenceGraph {
+    pub fn new(predicates: &[Predicate]) -> PrecedenceGraph {
+        let mut index: FxHashMap<usize, usize> = FxHashMap::default();
+        for predicate in predicates {
+            for page in [predicate.before, predicate.after] {
+                let next = index.len();
+                index.entry(page).or_insert(next);
+            }
+        }
+        let words = index.len().div_ceil(64);
+        let mut before = vec![vec![0_u64; words]; index.len()];
+        for predicate in predicates {
+            let (from, to) = (index[&predicate.before], index[&predicate.after]);
+            before[from][to / 64] |= 1 << (to % 64);
+        }
+        PrecedenceGraph { index, before }
+    }
+
+    pub fn must_precede(&self, first: usize, second: usize) -> bool {
+        match (self.index.get(&first), self.index.get(&second)) {
+            (Some(from), Some(to)) => self.before[*from][to / 64] & (1 << (to % 64)) != 0,
+            _ => false,
+        }
+    }
+
+    // edges between positions rather than pages, so repeated pages stay apart
+    fn edges(&self, update: &[usize]) -> Vec<Vec<usize>> {
+        (0..update.len())
+            .map(|from| {
+                (0..update.len())
+                    .filter(|to| *to != from && self.must_precede(update[from], update[*to]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Kahn's algorithm restricted to the update, ties are broken by the original position
+    pub fn order(&self, update: &[usize]) -> Result<Vec<usize>, CycleError> {
+        let edges = self.edges(update);
+        let mut in_degree = vec![0; update.len()];
+        for targets in &edges {
+            for to in targets {
+                in_degree[*to] += 1;
+            }
+        }
+
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..update.len())
+            .filter(|position| in_degree[*position] == 0)
+            .map(Reverse)
+            .collect();
+        let mut ordered = Vec::with_capacity(update.len());
+        while let Some(Reverse(position)) = ready.pop() {
+            ordered.push(update[position]);
+            for to in &edges[position] {
+                in_degree[*to] -= 1;
+                if in_degree[*to] == 0 {
+                    ready.push(Reverse(*to));
+                }
+            }
+        }
+
+        if ordered.len() == update.len() {
+            Ok(ordered)
+        } else {
+            Err(CycleError {
+                cycle: self.find_cycle(update, &edges, &in_degree),
+            })
+        }
+    }
+
+    // the update with the duplicates policy applied
+    fn pages(update: &[usize], duplicates: Duplicates) -> Result<SleighUpdates> {
+        let mut seen = FxHashSet::default();
+        Ok(match duplicates {
+            Duplicates::Keep => update.to_vec(),
+            Duplicates::Merge => update
+                .iter()
+                .filter(|page| seen.insert(**page))
+                .cloned()
+                .collect(),
+            Duplicates::Reject => {
+                if let Some(page) = update.iter().find(|page| !seen.insert(**page)) {
+                    bail!("page {page} appears more than once in the update");
+                }
+                update.to_vec()
+            }
+        })
+    }
+
+    pub fn order_with(&self, update: &[usize], options: &OrderOptions) -> Result<SleighUpdates> {
+        let pages = Self::pages(update, options.duplicates)?;
+        let ordered = self.order(&pages)?;
+        // the order is unique only when every neighbouring pair is pinned down by a rule
+        if options.undefined == Undefined::Reject {
+            for pair in ordered.windows(2) {
+                if pair[0] != pair[1] && !self.must_precede(pair[0], pair[1]) {
+                    bail!(
+                        "no rule decides the order of pages {} and {}",
+                        pair[0],
+                        pair[1]
+                    );
+                }
+            }
+        }
+        Ok(ordered)
+    }
+
+    // Copies of one page are chained in their original order, so each distinct sequence of
+    // pages is produced by exactly one ordering of positions. Positions are bits of a u128, so
+    // an update can have at most `MAX_PAGES` pages.
+    fn predecessor_masks(&self, update: &[usize]) -> Result<Vec<u128>> {
+        if update.len() > MAX_PAGES {
+            bail!(
+                "update with {} pages is too long, orders are only counted for up to {MAX_PAGES}",
+                update.len()
+            );
+        }
+        self.order(update)?;
+        Ok((0..update.len())
+            .map(|to| {
+                let previous_copy = (0..to).rev().find(|from| update[*from] == update[to]);
+                (0..update.len())
+                    .filter(|from| {
+                        Some(*from) == previous_copy
+                            || (*from != to && self.must_precede(update[*from], update[to]))
+                    })
+                    .fold(0, |mask, from| mask | 1 << from)
+            })
+            .collect())
+    }
+
+    // with `Undefined::Reject` every pair has to be decided, so there is one order or an error
+    pub fn count_orders(&self, update: &[usize], options: &OrderOptions) -> Result<u128> {
+        fn count(
+            predecessors: &[u128],
+            placed: u128,
+            cache: &mut FxHashMap<u128, u128>,
+        ) -> Result<u128> {
+            if placed.count_ones() as usize == predecessors.len() {
+                return Ok(1);
+            }
+            if let Some(cached) = cache.get(&placed) {
+                return Ok(*cached);
+            }
+            if cache.len() >= MAX_SUBSETS {
+                bail!("too many ways to start an order, gave up after {MAX_SUBSETS}");
+            }
+            let mut total: u128 = 0;
+            for (position, before) in predecessors.iter().enumerate() {
+                if placed & 1 << position == 0 && before & !placed == 0 {
+                    let rest = count(predecessors, placed | 1 << position, cache)?;
+                    total = total
+                        .checked_add(rest)
+                        .context("too many orders to count")?;
+                }
+            }
+            cache.insert(placed, total);
+            Ok(total)
+        }
+
+        if options.undefined == Undefined::Reject {
+            self.order_with(update, options)?;
+            return Ok(1);
+        }
+        let pages = Self::pages(update, options.duplicates)?;
+        let predecessors = self.predecessor_masks(&pages)?;
+        count(&predecessors, 0, &mut FxHashMap::default())
+    }
+
+    // fails instead of listing more than `MAX_ORDERS` orders
+    pub fn enumerate_orders(
+        &self,
+        update: &[usize],
+        options: &OrderOptions,
+    ) -> Result<Vec<SleighUpdates>> {
+        fn walk(
+            update: &[usize],
+            predecessors: &[u128],
+            placed: u128,
+            current: &mut SleighUpdates,
+            found: &mut Vec<SleighUpdates>,
+        ) -> Result<()> {
+            if current.len() == update.len() {
+                if found.len() == MAX_ORDERS {
+                    bail!("update has more than {MAX_ORDERS} orders");
+                }
+                found.push(current.clone());
+                return Ok(());
+            }
+            for (position, before) in predecessors.iter().enumerate() {
+                if placed & 1 << position == 0 && before & !placed == 0 {
+                    current.push(update[position]);
+                    walk(update, predecessors, placed | 1 << position, current, found)?;
+                    current.pop();
+                }
+            }
+            Ok(())
+        }
+
+        if options.undefined == Undefined::Reject {
+            return Ok(vec![self.order_with(update, options)?]);
+        }
+        let pages = Self::pages(update, options.duplicates)?;
+        let predecessors = self.predecessor_masks(&pages)?;
+        let mut found = Vec::new();
+        walk(&pages, &predecessors, 0, &mut Vec::new(), &mut found)?;
+        Ok(found)
+    }
+
+    // every position Kahn could not emit still has a predecessor among the leftovers,
+    // so walking predecessors from any of them has to loop eventually
+    fn find_cycle(
+        &self,
+        update: &[usize],
+        edges: &[Vec<usize>],
+        in_degree: &[usize],
+    ) -> Vec<usize> {
+        let remaining: Vec<bool> = in_degree.iter().map(|degree| *degree > 0).collect();
+        let mut seen_at: FxHashMap<usize, usize> = FxHashMap::default();
+        let mut walk = Vec::new();
+        let mut current = remaining.iter().position(|left| *left).unwrap();
+        while !seen_at.contains_key(&current) {
+            seen_at.insert(current, walk.len());
+            walk.push(current);
+            current = (0..update.len())
+                .find(|from| remaining[*from] && edges[*from].contains(&current))
+                .unwrap();
+        }
+        walk[seen_at[&current]..]
+            .iter()
+            .rev()
+            .map(|position| update[*position])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(pairs: &[(usize, usize)]) -> Vec<Predicate> {
+        pairs
+            .iter()
+            .map(|(before, after)| Predicate {
+                before: *before,
+                after: *after,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_order() {
+        let graph = PrecedenceGraph::new(&rules(&[(97, 75), (75, 47), (47, 61), (61, 53)]));
+        assert_eq!(
+            graph.order(&[75, 97, 47, 61, 53]),
+            Ok(vec![97, 75, 47, 61, 53])
+        );
+        assert_eq!(graph.order(&[61, 13, 29]), Ok(vec![61, 13, 29]));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let graph = PrecedenceGraph::new(&rules(&[(1, 2), (2, 3), (3, 1), (4, 1)]));
+        let error = graph.order(&[4, 1, 2, 3]).unwrap_err();
+        let mut cycle = error.cycle.clone();
+        let start = cycle.iter().position(|page| *page == 1).unwrap();
+        cycle.rotate_left(start);
+        assert_eq!(cycle, [1, 2, 3]);
+        assert!(error
+            .to_string()
+            .starts_with("ordering rules contain a cycle: "));
+
+        // the cycle only matters when all of its pages are in the update
+        assert_eq!(graph.order(&[3, 2, 4]), Ok(vec![2, 3, 4]));
+        assert!(graph
+            .count_orders(&[1, 2, 3], &OrderOptions::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let graph = PrecedenceGraph::new(&rules(&[(1, 2), (2, 3)]));
+        let keep = OrderOptions::default();
+        let merge = OrderOptions {
+            duplicates: Duplicates::Merge,
+            ..keep
+        };
+        let reject = OrderOptions {
+            duplicates: Duplicates::Reject,
+            ..keep
+        };
+        assert_eq!(
+            graph.order_with(&[3, 2, 3, 1], &keep).unwrap(),
+            [1, 2, 3, 3]
+        );
+        assert_eq!(graph.order_with(&[3, 2, 3, 1], &merge).unwrap(), [1, 2, 3]);
+        assert!(graph.order_with(&[3, 2, 3, 1], &reject).is_err());
+        assert!(graph.order_with(&[3, 2, 1], &reject).is_ok());
+    }
+
+    #[test]
+    fn test_undefined() {
+        let graph = PrecedenceGraph::new(&rules(&[(1, 2), (1, 3)]));
+        let stable = OrderOptions::default();
+        let reject = OrderOptions {
+            undefined: Undefined::Reject,
+            ..stable
+        };
+        assert_eq!(graph.order_with(&[3, 2, 1], &stable).unwrap(), [1, 3, 2]);
+        let error = graph.order_with(&[3, 2, 1], &reject).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "no rule decides the order of pages 3 and 2"
+        );
+        assert_eq!(graph.order_with(&[2, 1], &reject).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn test_count_orders() {
+        let graph = PrecedenceGraph::new(&rules(&[(1, 2), (1, 3), (2, 4), (3, 4)]));
+        let keep = OrderOptions::default();
+        assert_eq!(graph.count_orders(&[4, 3, 2, 1], &keep).unwrap(), 2);
+        assert_eq!(graph.count_orders(&[2, 1], &keep).unwrap(), 1);
+        assert_eq!(graph.count_orders(&[5, 6, 7], &keep).unwrap(), 6);
+        assert_eq!(graph.count_orders(&[5, 5, 6], &keep).unwrap(), 3);
+        assert_eq!(graph.count_orders(&[], &keep).unwrap(), 1);
+        let error = graph.count_orders(&[5; MAX_PAGES + 1], &keep).unwrap_err();
+        assert!(error.to_string().contains("up to 128"));
+    }
+
+    #[test]
+    fn test_enumerate_orders() {
+        let graph = PrecedenceGraph::new(&rules(&[(1, 2), (1, 3), (2, 4), (3, 4)]));
+        let keep = OrderOptions::default();
+        assert_eq!(
+            graph.enumerate_orders(&[4, 3, 2, 1], &keep).unwrap(),
+            [[1, 3, 2, 4], [1, 2, 3, 4]]
+        );
+        assert_eq!(
+            graph.enumerate_orders(&[5, 5, 6], &keep).unwrap(),
+            [[5, 5, 6], [5, 6, 5], [6, 5, 5]]
+        );
+    }
+
+    #[test]
+    fn test_orders_with_options() {
+        let graph = PrecedenceGraph::new(&rules(&[(1, 2), (1, 3), (2, 4), (3, 4)]));
+        let merge = OrderOptions {
+            duplicates: Duplicates::Merge,
+            ..OrderOptions::default()
+        };
+        let reject = OrderOptions {
+            undefined: Undefined::Reject,
+            ..OrderOptions::default()
+        };
+        assert_eq!(graph.count_orders(&[5, 5, 6], &merge).unwrap(), 2);
+        assert_eq!(
+            graph.enumerate_orders(&[2, 1, 2], &merge).unwrap(),
+            [[1, 2]]
+        );
+        assert!(graph.count_orders(&[4, 3, 2, 1], &reject).is_err());
+        assert_eq!(graph.count_orders(&[4, 2, 1], &reject).unwrap(), 1);
+        assert_eq!(
+            graph.enumerate_orders(&[4, 2, 1], &reject).unwrap(),
+            [[1, 2, 4]]
+        );
+    }
+
+    #[test]
+    fn test_too_many_orders() {
+        let graph = PrecedenceGraph::new(&[]);
+        let keep = OrderOptions::default();
+        let unrelated: Vec<usize> = (0..20).collect();
+        let error = graph.count_orders(&unrelated, &keep).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("too many ways to start an order"));
+        let error = graph.enumerate_orders(&unrelated[..9], &keep).unwrap_err();
+        assert_eq!(error.to_string(), "update has more than 100000 orders");
+        assert_eq!(
+            graph
+                .enumerate_orders(&unrelated[..8], &keep)
+                .unwrap()
+                .len(),
+            40320
+        );
+    }
+}
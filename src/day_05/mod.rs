@@ -1,31 +1,32 @@
-use std::cmp::Ordering;
-
+pub mod explain;
+pub mod graph;
 use anyhow::{self, Context, Ok, Result};
+use explain::{explain, violations, Explanation};
+use graph::{OrderOptions, PrecedenceGraph};
 
-type SleighUpdates = Vec<usize>;
+pub type SleighUpdates = Vec<usize>;
 
-#[derive(Debug)]
-struct Predicate {
-    before: usize,
-    after: usize,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Predicate {
+    pub before: usize,
+    pub after: usize,
 }
 
 pub fn part1(input: &str) -> Result<usize> {
     let (predicates, pages) = parse_input(input)?;
+    let graph = PrecedenceGraph::new(&predicates);
 
-    let result = pages
-        .iter()
-        .filter(|manual| is_safe(&predicates, &manual))
-        .map(|safe_manual| get_middle(safe_manual))
-        .sum();
+    let mut result = 0;
+    for manual in &pages {
+        if is_safe(&graph, manual) {
+            result += get_middle(manual);
+        }
+    }
     Ok(result)
 }
 
-
-fn is_safe(predicates: &[Predicate], manual: &SleighUpdates) -> bool {
-    let safe_manual = make_manual_safe(predicates, &manual);
-
-    safe_manual.iter().zip(manual).all(|(a, b)| a == b)
+fn is_safe(graph: &PrecedenceGraph, manual: &SleighUpdates) -> bool {
+    violations(graph, manual).is_empty()
 }
 
 fn get_middle(manual: &SleighUpdates) -> usize {
@@ -60,37 +61,44 @@ fn parse_input(input: &str) -> Result<(Vec<Predicate>, Vec<SleighUpdates>)> {
 
 pub fn part2(input: &str) -> Result<usize, anyhow::Error> {
     let (predicates, pages) = parse_input(input)?;
+    let graph = PrecedenceGraph::new(&predicates);
 
+    let mut result = 0;
+    for manual in &pages {
+        if !is_safe(&graph, manual) {
+            result += get_middle(&make_manual_safe(&graph, manual)?);
+        }
+    }
+    Ok(result)
+}
 
-    let result = pages
+pub fn explain_unsafe(input: &str) -> Result<Vec<Explanation>> {
+    let (predicates, pages) = parse_input(input)?;
+    let graph = PrecedenceGraph::new(&predicates);
+
+    pages
         .iter()
-        .filter(|manual| !is_safe(&predicates, manual))
-        .map(|unsafe_manual| make_manual_safe(&predicates, unsafe_manual))
-        .map(|safe_manual| get_middle(&safe_manual))
-        .sum();
-    Ok(result)
+        .filter(|manual| !is_safe(&graph, manual))
+        .map(|manual| explain(&graph, manual))
+        .collect()
 }
 
-fn make_manual_safe(predicates: &[Predicate], unsafe_manual: &Vec<usize>) -> Vec<usize> {
-    let mut safe_manual = unsafe_manual.clone();
-    safe_manual.sort_by(|a, b| {
-        // assume all pages are unique
-        let matching_predicate = predicates.iter().find(|pred| {
-            (pred.after == *a && pred.before == *b) || (pred.after == *b && pred.before == *a)
-        });
-
-        if let Some(predicate) = matching_predicate {
-            if predicate.before == *b && predicate.after == *a {
-                Ordering::Greater
-            } else {
-                Ordering::Less
-            }
-        } else {
-            Ordering::Equal
-        }
-    });
+// fails on updates longer than `graph::MAX_PAGES` pages, or too loosely ordered to count
+pub fn count_orders(input: &str) -> Result<Vec<(SleighUpdates, u128)>> {
+    let (predicates, pages) = parse_input(input)?;
+    let graph = PrecedenceGraph::new(&predicates);
+
+    pages
+        .into_iter()
+        .map(|manual| {
+            let count = graph.count_orders(&manual, &OrderOptions::default())?;
+            Ok((manual, count))
+        })
+        .collect()
+}
 
-    safe_manual
+fn make_manual_safe(graph: &PrecedenceGraph, unsafe_manual: &SleighUpdates) -> Result<Vec<usize>> {
+    Ok(graph.order(unsafe_manual)?)
 }
 
 #[cfg(test)]
@@ -164,4 +172,12 @@ mod tests {
         let result = part2(input);
         assert_eq!(result.unwrap(), 123);
     }
+
+    #[test]
+    fn test_cycle() {
+        let input = "1|2\n2|3\n3|1\n\n1,2\n3,1,2";
+        let result = part2(input).unwrap_err();
+        let cycle = result.downcast::<graph::CycleError>().unwrap();
+        assert_eq!(cycle.cycle.len(), 3);
+    }
 }
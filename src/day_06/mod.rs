@@ -1,9 +1,10 @@
 pub mod part2;
-use anyhow::{Context, Ok, Result};
+use anyhow::{ensure, Ok, Result};
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::fmt;
 
-#[derive(Debug)]
-enum Direction {
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Direction {
     Up,
     Down,
     Left,
@@ -11,7 +12,26 @@ enum Direction {
 }
 
 impl Direction {
-    fn to_the_right(&self) -> Direction {
+    fn from_marker(marker: char) -> Option<Direction> {
+        match marker {
+            '^' => Some(Direction::Up),
+            'v' => Some(Direction::Down),
+            '<' => Some(Direction::Left),
+            '>' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        }
+    }
+
+    fn to_the_right(self) -> Direction {
         match self {
             Direction::Up => Direction::Right,
             Direction::Down => Direction::Left,
@@ -21,8 +41,8 @@ impl Direction {
     }
 }
 
-#[derive(Debug)]
-struct Position(usize, usize);
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position(pub usize, pub usize);
 
 impl Position {
     fn add_direction(&self, direction: &Direction) -> Option<Position> {
@@ -47,26 +67,134 @@ impl Position {
     }
 }
 
+pub type PositionWithDirection = (Position, Direction);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PatrolOptions {
+    // a guard in front of another one counts as an obstacle
+    pub guards_block: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PatrolReport {
+    // visited cells per guard, in reading order of their starting positions
+    pub visited: Vec<usize>,
+    pub union: usize,
+}
+
+struct Guard {
+    location: Position,
+    direction: Direction,
+    // walked off the map, or stuck in a loop it will never leave
+    done: bool,
+    // every cell the guard stepped off with the direction it left it in, the last one is where
+    // the guard walked off the map
+    path: Vec<PositionWithDirection>,
+    // index into `path` where the loop starts, `path.len()` when the guard is boxed in and spins
+    loop_start: Option<usize>,
+    seen: FxHashMap<PositionWithDirection, usize>,
+    turns: usize,
+}
+
+impl Guard {
+    fn new((location, direction): PositionWithDirection) -> Guard {
+        Guard {
+            location,
+            direction,
+            done: false,
+            path: Vec::new(),
+            loop_start: None,
+            seen: FxHashMap::default(),
+            turns: 0,
+        }
+    }
+
+    fn visited(&self) -> impl Iterator<Item = &Position> {
+        self.path
+            .iter()
+            .map(|(position, _)| position)
+            .chain(std::iter::once(&self.location))
+    }
+}
+
 struct GuardPatrol {
-    current_direction: Direction,
+    // guard markers stay on the board, only '#' blocks
     board: Vec<Vec<char>>,
-    guard_location: Position,
+    starts: Vec<PositionWithDirection>,
 }
 
 impl GuardPatrol {
-    fn process_patrol(&mut self) {
-        while let Some(next_position) = self.guard_location.add_direction(&self.current_direction) {
-            if !self.within_patrol_area(&next_position) {
-                break;
+    // All guards move in the same tick, one after another in reading order. When they block each
+    // other only the whole group can loop, so the joint state is what gets remembered. The board
+    // is never touched, an extra obstacle is only checked for on the way.
+    fn walk(
+        &self,
+        starts: &[PositionWithDirection],
+        options: &PatrolOptions,
+        obstacle: Option<&Position>,
+    ) -> Vec<Guard> {
+        let mut guards: Vec<Guard> = starts.iter().cloned().map(Guard::new).collect();
+        let mut joint_states: FxHashSet<Vec<(Position, Direction, bool)>> = FxHashSet::default();
+
+        while guards.iter().any(|guard| !guard.done) {
+            for index in 0..guards.len() {
+                if guards[index].done {
+                    continue;
+                }
+                let guard = &guards[index];
+                let Some(next_position) = guard
+                    .location
+                    .add_direction(&guard.direction)
+                    .filter(|next| self.within_patrol_area(next))
+                else {
+                    let guard = &mut guards[index];
+                    guard.path.push((guard.location.clone(), guard.direction));
+                    guard.done = true;
+                    continue;
+                };
+                let blocked = self.will_collide(&next_position)
+                    || obstacle == Some(&next_position)
+                    || (options.guards_block
+                        && guards
+                            .iter()
+                            .any(|other| !other.done && other.location == next_position));
+
+                let guard = &mut guards[index];
+                if blocked {
+                    guard.direction = guard.direction.to_the_right();
+                    guard.turns += 1;
+                    // boxed in on all four sides, the guard just keeps spinning
+                    if !options.guards_block && guard.turns == 4 {
+                        guard.loop_start = Some(guard.path.len());
+                        guard.done = true;
+                    }
+                    continue;
+                }
+                guard.turns = 0;
+                let state = (guard.location.clone(), guard.direction);
+                if !options.guards_block {
+                    if let Some(first) = guard.seen.get(&state) {
+                        guard.loop_start = Some(*first);
+                        guard.done = true;
+                        continue;
+                    }
+                    guard.seen.insert(state.clone(), guard.path.len());
+                }
+                guard.path.push(state);
+                guard.location = next_position;
             }
-            if self.will_collide(&next_position) {
-                self.current_direction = self.current_direction.to_the_right();
-                continue;
+
+            if options.guards_block {
+                let state = guards
+                    .iter()
+                    .map(|guard| (guard.location.clone(), guard.direction, guard.done))
+                    .collect();
+                if !joint_states.insert(state) {
+                    break;
+                }
             }
-            self.board[self.guard_location.0][self.guard_location.1] = 'X';
-            self.guard_location = next_position;
         }
-        self.board[self.guard_location.0][self.guard_location.1] = 'X';
+        guards
     }
 
     fn within_patrol_area(&self, next_position: &Position) -> bool {
@@ -77,67 +205,82 @@ impl GuardPatrol {
         self.board[next_position.0][next_position.1] == '#'
     }
 
-    fn count_steps(&self) -> usize {
-        self.board
-            .iter()
-            .map(|line| {
-                line.iter()
-                    .fold(0, |acc, value| if *value == 'X' { acc + 1 } else { acc })
-            })
-            .sum()
+    fn report(&self, guards: &[Guard]) -> PatrolReport {
+        let union: FxHashSet<&Position> = guards.iter().flat_map(Guard::visited).collect();
+        PatrolReport {
+            visited: guards
+                .iter()
+                .map(|guard| guard.visited().collect::<FxHashSet<_>>().len())
+                .collect(),
+            union: union.len(),
+        }
     }
-}
 
-impl ToString for GuardPatrol {
-    fn to_string(&self) -> String {
-        let pretty_board: String = self
-            .board
+    fn render(&self, guards: &[Guard]) -> String {
+        let mut board = self.board.clone();
+        for position in guards.iter().flat_map(Guard::visited) {
+            board[position.0][position.1] = 'X';
+        }
+        board
             .iter()
-            .map(|chars| String::from_iter(chars))
+            .map(String::from_iter)
             .collect::<Vec<String>>()
-            .join("\n");
-        pretty_board
+            .join("\n")
+    }
+}
+
+impl fmt::Display for GuardPatrol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(&[]))
     }
 }
 
 impl fmt::Debug for GuardPatrol {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let pretty_board: String = self
-            .board
-            .iter()
-            .map(|chars| String::from_iter(chars))
-            .collect::<Vec<String>>()
-            .join("\n");
         f.write_str("-------\n")?;
-        f.write_str(&pretty_board)?;
+        f.write_str(&self.to_string())?;
         f.write_str("\n")?;
-        f.write_fmt(format_args!(
-            "Position: ({}, {})",
-            &self.guard_location.0, &self.guard_location.1
-        ))?;
-        f.write_str("\n")
+        for (location, direction) in &self.starts {
+            f.write_fmt(format_args!(
+                "Position: ({}, {}) {:?}",
+                &location.0, &location.1, direction
+            ))?;
+            f.write_str("\n")?;
+        }
+        std::result::Result::Ok(())
     }
 }
 
 fn parse_input(input: &str) -> Result<GuardPatrol> {
     let board: Vec<Vec<char>> = input.lines().map(|f| f.chars().collect()).collect();
-    let initial_guard_position = board
+    let starts: Vec<PositionWithDirection> = board
         .iter()
         .enumerate()
-        .find_map(|(i, row)| row.iter().position(|&c| c == '^').map(|j| Position(i, j)))
-        .context("failed to find guard position :(");
+        .flat_map(|(i, row)| {
+            row.iter().enumerate().filter_map(move |(j, cell)| {
+                Direction::from_marker(*cell).map(|direction| (Position(i, j), direction))
+            })
+        })
+        .collect();
+    ensure!(!starts.is_empty(), "failed to find guard position :(");
+
+    Ok(GuardPatrol { board, starts })
+}
+
+pub fn patrol(input: &str, options: &PatrolOptions) -> Result<PatrolReport> {
+    let patrol = parse_input(input)?;
+    let guards = patrol.walk(&patrol.starts, options, None);
+    Ok(patrol.report(&guards))
+}
 
-    Ok(GuardPatrol {
-        board,
-        current_direction: Direction::Up,
-        guard_location: initial_guard_position?,
-    })
+pub fn render_patrol(input: &str, options: &PatrolOptions) -> Result<String> {
+    let patrol = parse_input(input)?;
+    let guards = patrol.walk(&patrol.starts, options, None);
+    Ok(patrol.render(&guards))
 }
 
 pub fn part1(input: &str) -> Result<usize> {
-    let mut patrol = parse_input(input)?;
-    patrol.process_patrol();
-    Ok(patrol.count_steps())
+    Ok(patrol(input, &PatrolOptions::default())?.union)
 }
 
 #[cfg(test)]
@@ -159,4 +302,76 @@ mod tests {
         let result = part1(input);
         assert_eq!(result.unwrap(), 41);
     }
+
+    #[test]
+    fn test_render_patrol() {
+        let input = r#"....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#..."#;
+        let expected = r#"....#.....
+....XXXXX#
+....X...X.
+..#.X...X.
+..XXXXX#X.
+..X.X.X.X.
+.#XXXXXXX.
+.XXXXXXX#.
+#XXXXXXX..
+......#X.."#;
+        let result = render_patrol(input, &PatrolOptions::default());
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_orientations() {
+        let input = "#....\n..>.#\n.....";
+        assert_eq!(part1(input).unwrap(), 3);
+        let input = ".....\n..<..\n.....";
+        assert_eq!(part1(input).unwrap(), 3);
+        let input = "..#..\n..v..\n.#...";
+        assert_eq!(part1(input).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_many_guards() {
+        let input = r#"..........
+.>......#.
+..........
+......^...
+.........."#;
+        let result = patrol(input, &PatrolOptions::default()).unwrap();
+        assert_eq!(
+            result,
+            PatrolReport {
+                visited: vec![10, 4],
+                union: 13,
+            }
+        );
+    }
+
+    #[test]
+    fn test_guards_block() {
+        // without blocking they walk through each other, otherwise the left one turns down
+        let input = "...\n>.<\n...";
+        let result = patrol(input, &PatrolOptions::default()).unwrap();
+        assert_eq!(result.visited, [3, 3]);
+        let options = PatrolOptions { guards_block: true };
+        let result = patrol(input, &options).unwrap();
+        assert_eq!(result.visited, [3, 2]);
+        assert_eq!(result.union, 5);
+    }
+
+    #[test]
+    fn test_looping_guard() {
+        let input = ".#...\n.^..#\n.....\n#....\n...#.";
+        let result = patrol(input, &PatrolOptions::default()).unwrap();
+        assert_eq!(result.union, 8);
+    }
 }
@@ -1,208 +1,224 @@
 use anyhow::{Context, Ok, Result};
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use rustc_hash::FxHashSet;
-use std::fmt;
-
-#[derive(Debug, Hash, PartialEq, Eq, Clone)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
 
-impl Direction {
-    fn to_the_right(&self) -> Direction {
-        match self {
-            Direction::Up => Direction::Right,
-            Direction::Down => Direction::Left,
-            Direction::Left => Direction::Up,
-            Direction::Right => Direction::Down,
+use super::{parse_input, Direction, GuardPatrol, PatrolOptions, Position, PositionWithDirection};
+
+impl GuardPatrol {
+    // The first guard walks with one extra obstacle, its loop is read back from the recorded
+    // path. Returns None when the guard leaves the map.
+    fn describe_loop(&self, obstacle: &Position) -> Option<LoopObstacle> {
+        let guard = self
+            .walk(&self.starts[..1], &PatrolOptions::default(), Some(obstacle))
+            .pop()?;
+        let first = guard.loop_start?;
+        // boxed in on all four sides, the guard just keeps spinning on one cell, a lap of one state
+        if first == guard.path.len() {
+            return Some(LoopObstacle {
+                obstacle: obstacle.clone(),
+                cycle_length: 1,
+                cycle_start: first,
+                turn_points: vec![guard.location.clone()],
+                path: vec![(guard.location, guard.direction)],
+            });
         }
+        let cycle = &guard.path[first..];
+        let turn_points = (0..cycle.len())
+            .filter(|index| {
+                let previous = (index + cycle.len() - 1) % cycle.len();
+                cycle[*index].1 != cycle[previous].1
+            })
+            .map(|index| cycle[index].0.clone())
+            .collect();
+        Some(LoopObstacle {
+            obstacle: obstacle.clone(),
+            cycle_length: cycle.len(),
+            cycle_start: first,
+            turn_points,
+            path: cycle.to_vec(),
+        })
     }
-}
 
-type PositionWithDirection = (Position, Direction);
-
-#[derive(Debug, Hash, PartialEq, Eq, Clone)]
-struct Position(usize, usize);
-
-impl Position {
-    fn add_direction(&self, direction: &Direction) -> Option<Position> {
-        match direction {
-            Direction::Up => {
-                let (value, overflow) = self.0.overflowing_sub(1);
-                if overflow {
-                    return None;
-                }
-                Some(Position(value, self.1))
-            }
-            Direction::Down => Some(Position(self.0 + 1, self.1)),
-            Direction::Left => {
-                let (value, overflow) = self.1.overflowing_sub(1);
-                if overflow {
-                    return None;
-                }
-                Some(Position(self.0, value))
+    fn render_loop(&self, found: &LoopObstacle) -> String {
+        let mut board = self.board.clone();
+        for (location, direction) in &found.path {
+            let mark = match direction {
+                Direction::Up | Direction::Down => '|',
+                Direction::Left | Direction::Right => '-',
+            };
+            let current = &mut board[location.0][location.1];
+            *current = match *current {
+                '.' => mark,
+                '|' | '-' if *current != mark => '+',
+                other => other,
+            };
+        }
+        for turn_point in &found.turn_points {
+            if Direction::from_marker(board[turn_point.0][turn_point.1]).is_none() {
+                board[turn_point.0][turn_point.1] = '+';
             }
-            Direction::Right => Some(Position(self.0, self.1 + 1)),
         }
+        board[found.obstacle.0][found.obstacle.1] = 'O';
+        board
+            .iter()
+            .map(String::from_iter)
+            .collect::<Vec<String>>()
+            .join("\n")
     }
 }
 
-#[derive(Clone)]
-struct GuardPatrol {
-    current_direction: Direction,
-    board: Vec<Vec<char>>,
-    guard_location: Position,
+#[derive(Debug, PartialEq, Eq)]
+pub struct LoopObstacle {
+    pub obstacle: Position,
+    // number of steps in one lap
+    pub cycle_length: usize,
+    // number of steps the guard takes before it is on the loop
+    pub cycle_start: usize,
+    // cells where the guard turns, in walking order starting from where it joins the loop
+    pub turn_points: Vec<Position>,
+    // every cell of one lap with the direction the guard leaves it in
+    pub path: Vec<PositionWithDirection>,
 }
 
-impl GuardPatrol {
-    fn process_patrol(&mut self) {
-        while let Some(next_position) = self.guard_location.add_direction(&self.current_direction) {
-            if !self.within_patrol_area(&next_position) {
-                break;
+// For every cell and direction, the cell where the guard stops in front of the next obstacle,
+// or None when the guard walks off the map. A whole straight walk becomes a single lookup.
+struct JumpTable {
+    width: usize,
+    stops: [Vec<Option<Position>>; 4],
+}
+
+impl JumpTable {
+    fn new(board: &[Vec<char>]) -> JumpTable {
+        let height = board.len();
+        let width = board[0].len();
+        let mut stops: [Vec<Option<Position>>; 4] = Default::default();
+        for direction_stops in stops.iter_mut() {
+            direction_stops.resize(height * width, None);
+        }
+
+        for x in 0..width {
+            let mut stop = None;
+            for y in 0..height {
+                if board[y][x] == '#' {
+                    stop = Some(Position(y + 1, x));
+                } else {
+                    stops[Direction::Up.index()][y * width + x] = stop.clone();
+                }
             }
-            if self.will_collide(&next_position) {
-                self.current_direction = self.current_direction.to_the_right();
-                continue;
+            let mut stop = None;
+            for y in (0..height).rev() {
+                if board[y][x] == '#' {
+                    stop = y.checked_sub(1).map(|above| Position(above, x));
+                } else {
+                    stops[Direction::Down.index()][y * width + x] = stop.clone();
+                }
             }
-            self.board[self.guard_location.0][self.guard_location.1] = 'X';
-            self.guard_location = next_position;
         }
-        self.board[self.guard_location.0][self.guard_location.1] = 'X';
-    }
-
-    fn within_patrol_area(&self, next_position: &Position) -> bool {
-        next_position.0 < self.board.len() && next_position.1 < self.board[0].len()
-    }
-
-    fn will_collide(&self, next_position: &Position) -> bool {
-        let value = self.board[next_position.0][next_position.1];
-        value == '#' || value == 'O'
-    }
-
-    fn count_steps(&self) -> usize {
-        self.board
-            .iter()
-            .map(|line| {
-                line.iter()
-                    .fold(0, |acc, value| if *value == 'X' { acc + 1 } else { acc })
-            })
-            .sum()
-    }
-
-    fn add_obstacle(&mut self, position: &Position) {
-        self.board[position.0][position.1] = 'O';
-    }
-
-    fn check_loop(&mut self) -> bool {
-        let mut visited_positions: FxHashSet<PositionWithDirection> = FxHashSet::default();
-
-        while let Some(next_position) = self.guard_location.add_direction(&self.current_direction) {
-            if !self.within_patrol_area(&next_position) {
-                break;
+        for y in 0..height {
+            let mut stop = None;
+            for x in 0..width {
+                if board[y][x] == '#' {
+                    stop = Some(Position(y, x + 1));
+                } else {
+                    stops[Direction::Left.index()][y * width + x] = stop.clone();
+                }
             }
-            let current_location_and_direction =
-                (self.guard_location.clone(), self.current_direction.clone());
-
-            if self.will_collide(&next_position) {
-                if visited_positions.contains(&current_location_and_direction) {
-                    return true;
+            let mut stop = None;
+            for x in (0..width).rev() {
+                if board[y][x] == '#' {
+                    stop = x.checked_sub(1).map(|left| Position(y, left));
                 } else {
-                    visited_positions.insert(current_location_and_direction);
+                    stops[Direction::Right.index()][y * width + x] = stop.clone();
                 }
-                self.current_direction = self.current_direction.to_the_right();
-                continue;
             }
-            self.guard_location = next_position;
         }
-        // self.board[self.guard_location.0][self.guard_location.1] = 'X';
-
-        false
+        JumpTable { width, stops }
     }
-}
 
-impl ToString for GuardPatrol {
-    fn to_string(&self) -> String {
-        let pretty_board: String = self
-            .board
-            .iter()
-            .map(|chars| String::from_iter(chars))
-            .collect::<Vec<String>>()
-            .join("\n");
-        pretty_board
+    // the extra obstacle is checked on the fly, so trying a candidate never touches the table
+    fn next_stop(
+        &self,
+        from: &Position,
+        direction: &Direction,
+        obstacle: &Position,
+    ) -> Option<Position> {
+        let stop = &self.stops[direction.index()][from.0 * self.width + from.1];
+        let &Position(y, x) = from;
+        let &Position(obstacle_y, obstacle_x) = obstacle;
+        match direction {
+            Direction::Up if obstacle_x == x && obstacle_y < y => match stop {
+                Some(Position(stop_y, _)) if *stop_y > obstacle_y => stop.clone(),
+                _ => Some(Position(obstacle_y + 1, x)),
+            },
+            Direction::Down if obstacle_x == x && obstacle_y > y => match stop {
+                Some(Position(stop_y, _)) if *stop_y < obstacle_y => stop.clone(),
+                _ => Some(Position(obstacle_y - 1, x)),
+            },
+            Direction::Left if obstacle_y == y && obstacle_x < x => match stop {
+                Some(Position(_, stop_x)) if *stop_x > obstacle_x => stop.clone(),
+                _ => Some(Position(y, obstacle_x + 1)),
+            },
+            Direction::Right if obstacle_y == y && obstacle_x > x => match stop {
+                Some(Position(_, stop_x)) if *stop_x < obstacle_x => stop.clone(),
+                _ => Some(Position(y, obstacle_x - 1)),
+            },
+            _ => stop.clone(),
+        }
     }
-}
 
-impl fmt::Debug for GuardPatrol {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let pretty_board: String = self
-            .board
-            .iter()
-            .map(|chars| String::from_iter(chars))
-            .collect::<Vec<String>>()
-            .join("\n");
-        f.write_str("-------\n")?;
-        f.write_str(&pretty_board)?;
-        f.write_str("\n")?;
-        f.write_fmt(format_args!(
-            "Position: ({}, {})",
-            &self.guard_location.0, &self.guard_location.1
-        ))?;
-        f.write_str("\n")
+    fn check_loop(&self, start: &Position, mut direction: Direction, obstacle: &Position) -> bool {
+        let mut turns: FxHashSet<PositionWithDirection> = FxHashSet::default();
+        let mut location = start.clone();
+
+        while let Some(stop) = self.next_stop(&location, &direction, obstacle) {
+            if !turns.insert((stop.clone(), direction)) {
+                return true;
+            }
+            location = stop;
+            direction = direction.to_the_right();
+        }
+        false
     }
 }
 
-fn parse_input(input: &str) -> Result<GuardPatrol> {
-    let board: Vec<Vec<char>> = input.lines().map(|f| f.chars().collect()).collect();
-    let initial_guard_position = board
-        .iter()
-        .enumerate()
-        .find_map(|(i, row)| row.iter().position(|&c| c == '^').map(|j| Position(i, j)))
-        .context("failed to find guard position :(");
-
-    Ok(GuardPatrol {
-        board,
-        current_direction: Direction::Up,
-        guard_location: initial_guard_position?,
-    })
-}
+fn find_loop_obstacles(patrol: &GuardPatrol) -> Vec<Position> {
+    let (start, direction) = patrol.starts[0].clone();
+    let table = JumpTable::new(&patrol.board);
+    let walked = patrol.walk(&patrol.starts[..1], &PatrolOptions::default(), None);
+    // the obstacle can't be placed on the guard itself
+    let mut candidates: Vec<Position> = walked[0]
+        .visited()
+        .filter(|position| **position != start)
+        .cloned()
+        .collect();
+    candidates.sort();
+    candidates.dedup();
 
-pub fn part1(input: &str) -> Result<usize> {
-    let mut patrol = parse_input(input)?;
-    patrol.process_patrol();
-    Ok(patrol.count_steps())
+    candidates
+        .into_par_iter()
+        .filter(|obstacle_position| table.check_loop(&start, direction, obstacle_position))
+        .collect()
 }
 
 pub fn part2(input: &str) -> Result<usize> {
     let initial_patrol = parse_input(input)?;
-    let mut patrol = initial_patrol.clone();
-    patrol.process_patrol();
-    let all_steps_positions: Vec<Position> = patrol
-        .board
-        .iter()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.iter().enumerate().filter_map(move |(x, character)| {
-                if *character == 'X' {
-                    Some(Position(y, x))
-                } else {
-                    None
-                }
-            })
-        })
-        .collect();
+    Ok(find_loop_obstacles(&initial_patrol).len())
+}
 
-    let result = all_steps_positions
+pub fn loop_obstacles(input: &str) -> Result<Vec<LoopObstacle>> {
+    let initial_patrol = parse_input(input)?;
+    find_loop_obstacles(&initial_patrol)
         .par_iter()
-        .filter(|obstacle_position| {
-            let mut new_patrol = initial_patrol.clone();
-            new_patrol.add_obstacle(&obstacle_position);
-            new_patrol.check_loop()
+        .map(|obstacle| {
+            initial_patrol
+                .describe_loop(obstacle)
+                .context(format!("guard escapes with an obstacle at {obstacle:?}"))
         })
-        .count();
-    Ok(result)
+        .collect()
+}
+
+pub fn render_loop(input: &str, found: &LoopObstacle) -> Result<String> {
+    Ok(parse_input(input)?.render_loop(found))
 }
 
 #[cfg(test)]
@@ -224,4 +240,148 @@ mod tests {
         let result = part2(input);
         assert_eq!(result.unwrap(), 6);
     }
+
+    const EXAMPLE: &str = r#"....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#..."#;
+
+    #[test]
+    fn test_loop_obstacles() {
+        let result = loop_obstacles(EXAMPLE).unwrap();
+        let obstacles: Vec<Position> = result.iter().map(|found| found.obstacle.clone()).collect();
+        assert_eq!(
+            obstacles,
+            [
+                Position(6, 3),
+                Position(7, 6),
+                Position(7, 7),
+                Position(8, 1),
+                Position(8, 3),
+                Position(9, 7)
+            ]
+        );
+        assert_eq!(result[0].cycle_length, 18);
+        assert_eq!(result[0].cycle_start, 0);
+        assert_eq!(
+            result[0].turn_points,
+            [
+                Position(6, 4),
+                Position(1, 4),
+                Position(1, 8),
+                Position(6, 8)
+            ]
+        );
+        assert_eq!(result[0].path[0], (Position(6, 4), Direction::Up));
+    }
+
+    #[test]
+    fn test_render_loop() {
+        let result = loop_obstacles(EXAMPLE).unwrap();
+        let expected = r#"....#.....
+....+---+#
+....|...|.
+..#.|...|.
+....|..#|.
+....|...|.
+.#.O^---+.
+........#.
+#.........
+......#..."#;
+        assert_eq!(render_loop(EXAMPLE, &result[0]).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_obstacle_before_turn() {
+        // (3, 2) is walked through twice, going up first and later going left,
+        // an obstacle there stops the guard on its first pass already
+        let input = r#"..#...
+.....#
+......
+......
+..^.#."#;
+        let patrol = parse_input(input).unwrap();
+        let brute_force: Vec<Position> = (0..5)
+            .flat_map(|y| (0..6).map(move |x| Position(y, x)))
+            .filter(|position| patrol.board[position.0][position.1] == '.')
+            .filter(|position| patrol.describe_loop(position).is_some())
+            .collect();
+        let found: Vec<Position> = loop_obstacles(input)
+            .unwrap()
+            .into_iter()
+            .map(|found| found.obstacle)
+            .collect();
+        assert_eq!(found, brute_force);
+        assert_eq!(patrol.describe_loop(&Position(3, 2)), None);
+    }
+
+    #[test]
+    fn test_boxed_in() {
+        // the guard starts in a pocket, an obstacle below closes it
+        let input = ".#.\n#^#\n...";
+        assert_eq!(part2(input).unwrap(), 1);
+        let found = loop_obstacles(input).unwrap();
+        assert_eq!(
+            found,
+            [LoopObstacle {
+                obstacle: Position(2, 1),
+                cycle_length: 1,
+                cycle_start: 0,
+                turn_points: vec![Position(1, 1)],
+                path: vec![(Position(1, 1), Direction::Up)],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_jump_table() {
+        let board: Vec<Vec<char>> = ["..#..", "....#", ".....", "#....", "...#."]
+            .iter()
+            .map(|line| line.chars().collect())
+            .collect();
+        let table = JumpTable::new(&board);
+        let far_away = Position(0, 0);
+        let from = Position(2, 2);
+        assert_eq!(
+            table.next_stop(&from, &Direction::Up, &far_away),
+            Some(Position(1, 2))
+        );
+        assert_eq!(table.next_stop(&from, &Direction::Down, &far_away), None);
+        assert_eq!(table.next_stop(&from, &Direction::Right, &far_away), None);
+        let from = Position(3, 3);
+        assert_eq!(
+            table.next_stop(&from, &Direction::Left, &far_away),
+            Some(Position(3, 1))
+        );
+        assert_eq!(
+            table.next_stop(&from, &Direction::Down, &far_away),
+            Some(Position(3, 3))
+        );
+
+        // a closer extra obstacle wins, one behind the real obstacle changes nothing
+        let from = Position(2, 2);
+        assert_eq!(
+            table.next_stop(&from, &Direction::Down, &Position(4, 2)),
+            Some(Position(3, 2))
+        );
+        assert_eq!(
+            table.next_stop(&from, &Direction::Left, &Position(2, 0)),
+            Some(Position(2, 1))
+        );
+        let from = Position(3, 3);
+        assert_eq!(
+            table.next_stop(&from, &Direction::Left, &Position(3, 2)),
+            Some(Position(3, 3))
+        );
+        assert_eq!(
+            table.next_stop(&Position(4, 2), &Direction::Up, &Position(1, 2)),
+            Some(Position(2, 2))
+        );
+    }
 }
@@ -1,129 +1,274 @@
-use anyhow::{Context, Ok, Result};
+pub mod number;
+pub mod operators;
+
+use anyhow::{ensure, Context, Ok, Result};
+use num_bigint::BigInt;
 use rayon::prelude::*;
+use std::fmt;
 
-fn concatenate(a: i64, b: i64) -> i64 {
-    let mut digits = 0;
-    let mut rest = b;
-    while rest > 0 {
-        digits += 1;
-        rest = rest / 10;
-    }
-    a * 10_i64.pow(digits) + b
+use number::Number;
+use operators::{Add, Concatenate, Multiply, Operator, Preimage};
+
+// an equation with the operators that make it true, evaluated left to right
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Witness<N = i64> {
+    pub expected: N,
+    pub parts: Vec<N>,
+    pub operators: Vec<String>,
 }
 
-#[derive(Debug)]
-struct Polynomial {
-    expected: i64,
-    parts: Vec<i64>,
+impl<N: fmt::Display> fmt::Display for Witness<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.parts[0])?;
+        for (operator, value) in self.operators.iter().zip(&self.parts[1..]) {
+            write!(f, " {operator} {value}")?;
+        }
+        std::result::Result::Ok(())
+    }
 }
-impl Polynomial {
-    fn has_solution(&self) -> Result<bool> {
-        let acc = self.parts[0];
-        let cursor = 1;
 
-        let calculate = self.solution_inner(acc, cursor);
-        let result = calculate == self.expected;
+// checked i64 arithmetic by default, where an overflowing step just drops that branch;
+// `Solver<BigInt>` for equations that don't fit
+pub struct Solver<N: Number = i64> {
+    operators: Vec<Box<dyn Operator<N>>>,
+}
 
-        // dbg!("------------");
-        // dbg!(&self.parts);
-        // dbg!(&self.expected);
-        // dbg!(&calculate);
-        // dbg!(&result);
-        Ok(result)
+impl<N: Number> Default for Solver<N> {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn solution_inner(&self, acc: i64, cursor: usize) -> i64 {
-        if cursor >= self.parts.len() || acc >= self.expected.into() {
-            return acc;
-        }
-        let value = self.parts[cursor];
-        let result = self.solution_inner(&acc * value, cursor + 1);
-        if result != self.expected.into() {
-            self.solution_inner(&acc + value, cursor + 1)
-        } else {
-            result
+impl<N: Number> Solver<N> {
+    pub fn new() -> Solver<N> {
+        Solver {
+            operators: Vec::new(),
         }
     }
 
-    fn has_extended_solution(&self) -> Result<bool> {
-        let acc = self.parts[0].into();
-        let cursor = 1;
+    pub fn part1() -> Solver<N> {
+        Solver::new().register(Add).register(Multiply)
+    }
+
+    pub fn part2() -> Solver<N> {
+        Solver::part1().register(Concatenate)
+    }
+
+    // operators are tried in registration order
+    pub fn register(mut self, operator: impl Operator<N> + 'static) -> Solver<N> {
+        self.operators.push(Box::new(operator));
+        self
+    }
 
-        let calculate = self.extended_solution_inner(acc, cursor);
-        let result = calculate == self.expected;
+    // left to right, `chosen` holds operator indices; None when some step is undefined
+    pub fn evaluate(&self, parts: &[N], chosen: &[usize]) -> Option<N> {
+        chosen
+            .iter()
+            .zip(&parts[1..])
+            .try_fold(parts[0].clone(), |acc, (operator, value)| {
+                self.operators[*operator].apply(&acc, value)
+            })
+    }
 
-        // dbg!("------------");
-        // dbg!(&self.parts);
-        // dbg!(&self.expected);
-        // dbg!(&calculate);
-        // dbg!(&result);
-        Ok(result)
+    // Right to left from the target: the last value has to be un-applied from it, which for most
+    // operators leaves one candidate or none. `chosen[i]` is the operator before `parts[i + 1]`.
+    fn backward(
+        &self,
+        parts: &[N],
+        target: N,
+        prune_negative: bool,
+        chosen: &mut [usize],
+        visit: &mut dyn FnMut(&[usize]) -> bool,
+    ) -> bool {
+        let last = parts.len() - 1;
+        if last == 0 {
+            return target != parts[0] || visit(chosen);
+        }
+        if prune_negative && target.is_negative() {
+            return true;
+        }
+        for (index, operator) in self.operators.iter().enumerate() {
+            chosen[last - 1] = index;
+            let keep_going = match operator.unapply(&target, &parts[last]) {
+                Preimage::None => true,
+                Preimage::One(acc) => {
+                    self.backward(&parts[..last], acc, prune_negative, chosen, visit)
+                }
+                Preimage::Two(first, second) => {
+                    self.backward(&parts[..last], first, prune_negative, chosen, visit)
+                        && self.backward(&parts[..last], second, prune_negative, chosen, visit)
+                }
+                Preimage::Any => self.forward(&parts[..last], 1, parts[0].clone(), chosen, visit),
+            };
+            if !keep_going {
+                return false;
+            }
+        }
+        true
     }
 
-    fn extended_solution_inner(&self, acc: i64, cursor: usize) -> i64 {
-        if cursor >= self.parts.len() || acc > self.expected {
-            return acc;
+    // every choice for the first `parts.len()` values that can be evaluated at all
+    fn forward(
+        &self,
+        parts: &[N],
+        cursor: usize,
+        acc: N,
+        chosen: &mut [usize],
+        visit: &mut dyn FnMut(&[usize]) -> bool,
+    ) -> bool {
+        if cursor == parts.len() {
+            return visit(chosen);
         }
-        let value = self.parts[cursor];
-        let result = self.extended_solution_inner(acc * value, cursor + 1);
-        if result != self.expected {
-            let result2 = self.extended_solution_inner(acc + value, cursor + 1);
-            if result2 != self.expected {
-                self.extended_solution_inner(concatenate(acc, value), cursor + 1)
-            } else {
-                result2
+        for (index, operator) in self.operators.iter().enumerate() {
+            let Some(next) = operator.apply(&acc, &parts[cursor]) else {
+                continue;
+            };
+            chosen[cursor - 1] = index;
+            if !self.forward(parts, cursor + 1, next, chosen, visit) {
+                return false;
             }
-        } else {
-            result
+        }
+        true
+    }
+}
+
+#[derive(Debug)]
+struct Polynomial<N> {
+    expected: N,
+    parts: Vec<N>,
+}
+impl<N: Number> Polynomial<N> {
+    // `visit` gets every operator choice that hits `expected` and returns false to stop the search
+    fn search(&self, solver: &Solver<N>, visit: &mut dyn FnMut(&[usize]) -> bool) {
+        let prune_negative = self.parts.iter().all(|part| !part.is_negative())
+            && solver
+                .operators
+                .iter()
+                .all(|operator| operator.keeps_sign());
+        let mut chosen = vec![0; self.parts.len() - 1];
+        solver.backward(
+            &self.parts,
+            self.expected.clone(),
+            prune_negative,
+            &mut chosen,
+            visit,
+        );
+    }
+
+    fn witness(&self, solver: &Solver<N>) -> Option<Witness<N>> {
+        let mut found = None;
+        self.search(solver, &mut |chosen| {
+            found = Some(self.to_witness(solver, chosen));
+            false
+        });
+        found
+    }
+
+    fn witnesses(&self, solver: &Solver<N>) -> Vec<Witness<N>> {
+        let mut found = Vec::new();
+        self.search(solver, &mut |chosen| {
+            found.push(self.to_witness(solver, chosen));
+            true
+        });
+        found
+    }
+
+    fn count_witnesses(&self, solver: &Solver<N>) -> usize {
+        let mut count = 0;
+        self.search(solver, &mut |_| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    fn to_witness(&self, solver: &Solver<N>, chosen: &[usize]) -> Witness<N> {
+        Witness {
+            expected: self.expected.clone(),
+            parts: self.parts.clone(),
+            operators: chosen
+                .iter()
+                .map(|operator| solver.operators[*operator].symbol().to_string())
+                .collect(),
         }
     }
 }
 
-fn parse(input: &str) -> Result<Vec<Polynomial>> {
-    Ok(input
+fn parse_number<N: Number>(text: &str) -> Result<N> {
+    text.trim().parse().ok().with_context(|| {
+        format!("{text:?} is not a number, or needs the arbitrary precision mode to fit")
+    })
+}
+
+fn parse<N: Number>(input: &str) -> Result<Vec<Polynomial<N>>> {
+    input
         .lines()
         .map(|line| {
             let (result, numbers) = line.split_once(":").context("malformed line")?;
-            let result: i64 = result.parse()?;
+            let parts = numbers
+                .split_whitespace()
+                .map(parse_number)
+                .collect::<Result<Vec<N>>>()?;
+            ensure!(!parts.is_empty(), "no numbers in {line:?}");
 
             Ok(Polynomial {
-                expected: result,
-                parts: numbers
-                    .split(" ")
-                    .flat_map(|n| n.parse().ok())
-                    .collect::<Vec<i64>>(),
+                expected: parse_number(result)?,
+                parts,
             })
         })
-        .collect::<Result<Vec<Polynomial>>>()?)
+        .collect()
 }
-pub fn part1(input: &str) -> Result<i64> {
+
+// sum of the targets that can be hit
+pub fn calibrate<N: Number>(input: &str, solver: &Solver<N>) -> Result<N> {
     let polyms = parse(input)?;
-    let result = polyms
+    polyms
         .par_iter()
-        .filter_map(|polym| {
-            if polym.has_solution().unwrap() {
-                Some(polym.expected)
-            } else {
-                None
-            }
-        })
-        .sum();
-    Ok(result)
+        .filter(|polym| polym.witness(solver).is_some())
+        .map(|polym| Some(polym.expected.clone()))
+        .try_reduce(N::zero, |a, b| a.checked_add(&b))
+        .context("the total overflows, it needs the arbitrary precision mode")
+}
+
+pub fn part1(input: &str) -> Result<i64> {
+    calibrate(input, &Solver::part1())
 }
 
 pub fn part2(input: &str) -> Result<i64> {
+    calibrate(input, &Solver::part2())
+}
+
+pub fn part1_big(input: &str) -> Result<BigInt> {
+    calibrate(input, &Solver::part1())
+}
+
+pub fn part2_big(input: &str) -> Result<BigInt> {
+    calibrate(input, &Solver::part2())
+}
+
+// the first witness of every equation that can be made true
+pub fn solved_equations<N: Number>(input: &str, solver: &Solver<N>) -> Result<Vec<Witness<N>>> {
     let polyms = parse(input)?;
-    let result = polyms
+    Ok(polyms
         .par_iter()
-        .filter_map(|polym| {
-            if polym.has_extended_solution().unwrap() {
-                Some(polym.expected)
-            } else {
-                None
-            }
-        })
-        .sum();
-    Ok(result)
+        .filter_map(|polym| polym.witness(solver))
+        .collect())
+}
+
+pub fn all_witnesses<N: Number>(input: &str, solver: &Solver<N>) -> Result<Vec<Vec<Witness<N>>>> {
+    let polyms = parse(input)?;
+    Ok(polyms
+        .par_iter()
+        .map(|polym| polym.witnesses(solver))
+        .collect())
+}
+
+pub fn count_witnesses<N: Number>(input: &str, solver: &Solver<N>) -> Result<Vec<usize>> {
+    let polyms = parse(input)?;
+    Ok(polyms
+        .par_iter()
+        .map(|polym| polym.count_witnesses(solver))
+        .collect())
 }
 
 #[cfg(test)]
@@ -165,4 +310,107 @@ mod tests {
         let result = part2(input);
         assert_eq!(result.unwrap(), 11387);
     }
+
+    const EXAMPLE: &str = r#"190: 10 19
+3267: 81 40 27
+83: 17 5
+156: 15 6
+7290: 6 8 6 15
+161011: 16 10 13
+192: 17 8 14
+21037: 9 7 18 13
+292: 11 6 16 20"#;
+
+    #[test]
+    fn test_witness() {
+        let solved = solved_equations(EXAMPLE, &Solver::<i64>::part1()).unwrap();
+        let solved: Vec<String> = solved.iter().map(|witness| witness.to_string()).collect();
+        assert_eq!(solved, ["10 * 19", "81 * 40 + 27", "11 + 6 * 16 + 20"]);
+
+        let solved = solved_equations(EXAMPLE, &Solver::<i64>::part2()).unwrap();
+        assert_eq!(solved.len(), 6);
+        assert_eq!(solved[3].to_string(), "6 * 8 || 6 * 15");
+        assert_eq!(solved[3].expected, 7290);
+    }
+
+    #[test]
+    fn test_all_witnesses() {
+        let witnesses = all_witnesses("3267: 81 40 27", &Solver::<i64>::part1()).unwrap();
+        let witnesses: Vec<String> = witnesses[0].iter().map(|w| w.to_string()).collect();
+        assert_eq!(witnesses, ["81 * 40 + 27", "81 + 40 * 27"]);
+
+        let counts = count_witnesses(EXAMPLE, &Solver::<i64>::part2()).unwrap();
+        assert_eq!(counts, [1, 2, 0, 1, 1, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_extra_operators() {
+        let solver = Solver::<i64>::part1()
+            .register(operators::Subtract)
+            .register(operators::Divide)
+            .register(operators::Power);
+        let input = "1: 4 3\n5: 20 4\n8: 2 3\n-1: 2 3\n16: 2 2 2\n7: 2 2 2";
+        let solved: Vec<String> = solved_equations(input, &solver)
+            .unwrap()
+            .iter()
+            .map(|witness| witness.to_string())
+            .collect();
+        assert_eq!(solved, ["4 - 3", "20 / 4", "2 ^ 3", "2 - 3", "2 + 2 ^ 2"]);
+
+        // every witness evaluates forward to its target
+        for witnesses in all_witnesses(input, &solver).unwrap() {
+            for witness in witnesses {
+                let chosen: Vec<usize> = witness
+                    .operators
+                    .iter()
+                    .map(|symbol| ["+", "*", "-", "/", "^"].iter().position(|s| s == symbol))
+                    .collect::<Option<_>>()
+                    .unwrap();
+                assert_eq!(
+                    solver.evaluate(&witness.parts, &chosen),
+                    Some(witness.expected)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_swallows_prefix() {
+        let witnesses = all_witnesses("7: 3 5 0 7", &Solver::<i64>::part1()).unwrap();
+        let witnesses: Vec<String> = witnesses[0].iter().map(|w| w.to_string()).collect();
+        assert_eq!(witnesses, ["3 + 5 * 0 + 7", "3 * 5 * 0 + 7"]);
+    }
+
+    #[test]
+    fn test_concatenate_zero() {
+        // 12 || 0 is 120, not 12
+        assert_eq!(part2("120: 12 0").unwrap(), 120);
+        assert_eq!(part2("12: 12 0\n1200: 12 0 0").unwrap(), 1212);
+    }
+
+    #[test]
+    fn test_overflow_is_pruned() {
+        // 9223372036854775807 * 3 wraps around to 9223372036854775805 with plain i64
+        assert_eq!(
+            part1("9223372036854775805: 9223372036854775807 3").unwrap(),
+            0
+        );
+        assert_eq!(part2("1: 9223372036854775807 10 1").unwrap(), 0);
+        assert!(part1("9223372036854775807: 9223372036854775807\n1: 1").is_err());
+        assert!(part1("9223372036854775808: 9223372036854775807 1").is_err());
+    }
+
+    #[test]
+    fn test_big() {
+        let input = "9223372036854775808: 9223372036854775807 1\n\
+                     92233720368547758070: 9223372036854775807 0";
+        let expected: BigInt = "9223372036854775808".parse().unwrap();
+        assert_eq!(part1_big(input).unwrap(), expected);
+        let expected = expected + "92233720368547758070".parse::<BigInt>().unwrap();
+        assert_eq!(part2_big(input).unwrap(), expected);
+        assert_eq!(part2_big(EXAMPLE).unwrap(), BigInt::from(11387));
+
+        let solved = solved_equations(input, &Solver::<BigInt>::part2()).unwrap();
+        assert_eq!(solved[1].to_string(), "9223372036854775807 || 0");
+    }
 }
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str::FromStr;
+
+use num_bigint::{BigInt, Sign};
+
+// What the solver needs from its numbers. Every step that can overflow is checked, a None
+// drops that branch of the search instead of wrapping around into a wrong answer.
+pub trait Number:
+    Clone + PartialEq + PartialOrd + fmt::Debug + fmt::Display + FromStr + Send + Sync + 'static
+{
+    fn from_i64(value: i64) -> Self;
+    fn is_negative(&self) -> bool;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    // None for a zero divisor or when there is a remainder
+    fn exact_div(&self, other: &Self) -> Option<Self>;
+    fn checked_pow(&self, exponent: u32) -> Option<Self>;
+    // largest root with root^exponent <= self, for non-negative self and exponent > 0
+    fn nth_root(&self, exponent: u32) -> Self;
+    fn to_u32(&self) -> Option<u32>;
+    // 10 to the number of decimal digits, so 10 for 0 as well
+    fn digit_shift(&self) -> Option<Self>;
+
+    fn zero() -> Self {
+        Self::from_i64(0)
+    }
+}
+
+impl Number for i64 {
+    fn from_i64(value: i64) -> Self {
+        value
+    }
+    fn is_negative(&self) -> bool {
+        *self < 0
+    }
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        i64::checked_add(*self, *other)
+    }
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        i64::checked_sub(*self, *other)
+    }
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        i64::checked_mul(*self, *other)
+    }
+    fn exact_div(&self, other: &Self) -> Option<Self> {
+        if i64::checked_rem(*self, *other)? != 0 {
+            return None;
+        }
+        i64::checked_div(*self, *other)
+    }
+    fn checked_pow(&self, exponent: u32) -> Option<Self> {
+        i64::checked_pow(*self, exponent)
+    }
+    fn nth_root(&self, exponent: u32) -> Self {
+        // the float guess is close, the exact powers settle it
+        let mut root = (*self as f64).powf(1.0 / exponent as f64) as i64;
+        while root > 0 && i64::checked_pow(root, exponent).is_none_or(|power| power > *self) {
+            root -= 1;
+        }
+        while root
+            .checked_add(1)
+            .and_then(|next| i64::checked_pow(next, exponent))
+            .is_some_and(|power| power <= *self)
+        {
+            root += 1;
+        }
+        root
+    }
+    fn to_u32(&self) -> Option<u32> {
+        u32::try_from(*self).ok()
+    }
+    fn digit_shift(&self) -> Option<Self> {
+        let mut shift: i64 = 10;
+        let mut rest = self.unsigned_abs() / 10;
+        while rest > 0 {
+            shift = i64::checked_mul(shift, 10)?;
+            rest /= 10;
+        }
+        Some(shift)
+    }
+}
+
+// a power is refused when its result could be longer than this many bits
+const MAX_POWER_BITS: u64 = 1 << 16;
+
+// arbitrary precision, nothing overflows and only undefined steps and powers past
+// `MAX_POWER_BITS` return None
+impl Number for BigInt {
+    fn from_i64(value: i64) -> Self {
+        BigInt::from(value)
+    }
+    fn is_negative(&self) -> bool {
+        self.sign() == Sign::Minus
+    }
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Some(self - other)
+    }
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+    fn exact_div(&self, other: &Self) -> Option<Self> {
+        if other.sign() == Sign::NoSign || (self % other).sign() != Sign::NoSign {
+            return None;
+        }
+        Some(self / other)
+    }
+    fn checked_pow(&self, exponent: u32) -> Option<Self> {
+        // 0, 1 and -1 stay small, anything else past the limit would take forever to work out
+        if self.bits() > 1 && self.bits() * exponent as u64 > MAX_POWER_BITS {
+            return None;
+        }
+        Some(self.pow(exponent))
+    }
+    fn nth_root(&self, exponent: u32) -> Self {
+        BigInt::nth_root(self, exponent)
+    }
+    fn to_u32(&self) -> Option<u32> {
+        u32::try_from(self).ok()
+    }
+    fn digit_shift(&self) -> Option<Self> {
+        let digits = self.magnitude().to_string().len();
+        Some(BigInt::from(10).pow(digits as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digit_shift() {
+        assert_eq!(0_i64.digit_shift(), Some(10));
+        assert_eq!(9_i64.digit_shift(), Some(10));
+        assert_eq!(10_i64.digit_shift(), Some(100));
+        assert_eq!(999_i64.digit_shift(), Some(1000));
+        assert_eq!(i64::MAX.digit_shift(), None);
+        assert_eq!(BigInt::from(0).digit_shift(), Some(BigInt::from(10)));
+        assert_eq!(BigInt::from(123).digit_shift(), Some(BigInt::from(1000)));
+    }
+
+    #[test]
+    fn test_nth_root() {
+        assert_eq!(80_i64.nth_root(4), 2);
+        assert_eq!(81_i64.nth_root(4), 3);
+        assert_eq!(i64::MAX.nth_root(2), 3_037_000_499);
+        assert_eq!(i64::MAX.nth_root(63), 1);
+        assert_eq!(0_i64.nth_root(3), 0);
+        assert_eq!(i64::MAX.nth_root(1), i64::MAX);
+    }
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(Number::checked_mul(&i64::MAX, &2), None);
+        assert_eq!(Number::checked_add(&i64::MAX, &1), None);
+        assert_eq!(i64::MIN.exact_div(&-1), None);
+        assert_eq!(7_i64.exact_div(&2), None);
+        assert_eq!(8_i64.exact_div(&0), None);
+        let big = BigInt::from(i64::MAX);
+        assert_eq!(
+            Number::checked_mul(&big, &BigInt::from(2)),
+            Some(BigInt::from(i64::MAX) * 2)
+        );
+    }
+
+    #[test]
+    fn test_big_power() {
+        let two = BigInt::from(2);
+        assert_eq!(two.checked_pow(100), Some(BigInt::from(1) << 100));
+        assert_eq!(two.checked_pow(u32::MAX), None);
+        assert_eq!(BigInt::from(i64::MAX).checked_pow(1 << 12), None);
+        assert_eq!(
+            BigInt::from(-1).checked_pow(u32::MAX),
+            Some(BigInt::from(-1))
+        );
+        assert_eq!(BigInt::from(1).checked_pow(u32::MAX), Some(BigInt::from(1)));
+    }
+}
@@ -0,0 +1,248 @@
+use super::number::Number;
+
+// every `acc` that an operator could have turned into the target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Preimage<N> {
+    None,
+    One(N),
+    Two(N, N),
+    // the value swallows whatever came before it, like multiplying by zero
+    Any,
+}
+
+pub trait Operator<N: Number = i64>: Send + Sync {
+    fn symbol(&self) -> &str;
+    // None when the result is not defined, e.g. division with a remainder, or overflows
+    fn apply(&self, acc: &N, value: &N) -> Option<N>;
+    // all `acc` with `apply(acc, value) == Some(target)`
+    fn unapply(&self, target: &N, value: &N) -> Preimage<N>;
+
+    // two non-negative operands never give a negative result, so a negative target can be dropped
+    fn keeps_sign(&self) -> bool {
+        true
+    }
+}
+
+pub struct Add;
+pub struct Multiply;
+pub struct Concatenate;
+pub struct Subtract;
+pub struct Divide;
+pub struct Power;
+
+fn preimage<N>(acc: Option<N>) -> Preimage<N> {
+    match acc {
+        Some(acc) => Preimage::One(acc),
+        None => Preimage::None,
+    }
+}
+
+// digits of `b` glued to the right of `a`, 12 || 0 is 120
+pub fn concatenate<N: Number>(a: &N, b: &N) -> Option<N> {
+    a.checked_mul(&b.digit_shift()?)?.checked_add(b)
+}
+
+impl<N: Number> Operator<N> for Add {
+    fn symbol(&self) -> &str {
+        "+"
+    }
+    fn apply(&self, acc: &N, value: &N) -> Option<N> {
+        acc.checked_add(value)
+    }
+    fn unapply(&self, target: &N, value: &N) -> Preimage<N> {
+        preimage(target.checked_sub(value))
+    }
+}
+
+impl<N: Number> Operator<N> for Multiply {
+    fn symbol(&self) -> &str {
+        "*"
+    }
+    fn apply(&self, acc: &N, value: &N) -> Option<N> {
+        acc.checked_mul(value)
+    }
+    fn unapply(&self, target: &N, value: &N) -> Preimage<N> {
+        if *value == N::zero() {
+            return if *target == N::zero() {
+                Preimage::Any
+            } else {
+                Preimage::None
+            };
+        }
+        preimage(target.exact_div(value))
+    }
+}
+
+// only defined for non-negative numbers
+impl<N: Number> Operator<N> for Concatenate {
+    fn symbol(&self) -> &str {
+        "||"
+    }
+    fn apply(&self, acc: &N, value: &N) -> Option<N> {
+        if acc.is_negative() || value.is_negative() {
+            return None;
+        }
+        concatenate(acc, value)
+    }
+    fn unapply(&self, target: &N, value: &N) -> Preimage<N> {
+        if value.is_negative() || target < value {
+            return Preimage::None;
+        }
+        // a shift that overflows can't have produced a target that fits
+        let acc = value
+            .digit_shift()
+            .and_then(|shift| target.checked_sub(value)?.exact_div(&shift));
+        preimage(acc)
+    }
+}
+
+impl<N: Number> Operator<N> for Subtract {
+    fn symbol(&self) -> &str {
+        "-"
+    }
+    fn apply(&self, acc: &N, value: &N) -> Option<N> {
+        acc.checked_sub(value)
+    }
+    fn unapply(&self, target: &N, value: &N) -> Preimage<N> {
+        preimage(target.checked_add(value))
+    }
+    fn keeps_sign(&self) -> bool {
+        false
+    }
+}
+
+// exact division only, so every result has a single way back
+impl<N: Number> Operator<N> for Divide {
+    fn symbol(&self) -> &str {
+        "/"
+    }
+    fn apply(&self, acc: &N, value: &N) -> Option<N> {
+        acc.exact_div(value)
+    }
+    fn unapply(&self, target: &N, value: &N) -> Preimage<N> {
+        if *value == N::zero() {
+            return Preimage::None;
+        }
+        preimage(target.checked_mul(value))
+    }
+}
+
+impl<N: Number> Operator<N> for Power {
+    fn symbol(&self) -> &str {
+        "^"
+    }
+    fn apply(&self, acc: &N, value: &N) -> Option<N> {
+        acc.checked_pow(value.to_u32()?)
+    }
+    fn unapply(&self, target: &N, value: &N) -> Preimage<N> {
+        let Some(exponent) = value.to_u32() else {
+            return Preimage::None;
+        };
+        if exponent == 0 {
+            return if *target == N::from_i64(1) {
+                Preimage::Any
+            } else {
+                Preimage::None
+            };
+        }
+        let odd = exponent % 2 == 1;
+        let negative = target.is_negative();
+        if negative && !odd {
+            return Preimage::None;
+        }
+        let magnitude = if negative {
+            N::zero().checked_sub(target)
+        } else {
+            Some(target.clone())
+        };
+        let Some(magnitude) = magnitude else {
+            return Preimage::None;
+        };
+        let root = magnitude.nth_root(exponent);
+        if root.checked_pow(exponent) != Some(magnitude) {
+            return Preimage::None;
+        }
+        // the root is at most the magnitude, so negating it can't overflow
+        let negated = N::zero().checked_sub(&root).unwrap();
+        match (root == N::zero(), odd, negative) {
+            (true, _, _) => Preimage::One(root),
+            (false, true, true) => Preimage::One(negated),
+            (false, true, false) => Preimage::One(root),
+            (false, false, _) => Preimage::Two(root, negated),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every acc that unapply hands out has to lead back to the target, and every acc that
+    // reaches the target has to be handed out
+    fn check_round_trip(operator: &dyn Operator) {
+        for value in -6..=12 {
+            for acc in -30..=30 {
+                let Some(target) = operator.apply(&acc, &value) else {
+                    continue;
+                };
+                match operator.unapply(&target, &value) {
+                    Preimage::None => panic!("{acc} {} {value} not found", operator.symbol()),
+                    Preimage::One(found) => assert_eq!(found, acc),
+                    Preimage::Two(first, second) => assert!(first == acc || second == acc),
+                    Preimage::Any => {}
+                }
+            }
+            for target in -30..=30 {
+                match operator.unapply(&target, &value) {
+                    Preimage::None | Preimage::Any => {}
+                    Preimage::One(acc) => assert_eq!(operator.apply(&acc, &value), Some(target)),
+                    Preimage::Two(first, second) => {
+                        assert_eq!(operator.apply(&first, &value), Some(target));
+                        assert_eq!(operator.apply(&second, &value), Some(target));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trips() {
+        check_round_trip(&Add);
+        check_round_trip(&Multiply);
+        check_round_trip(&Concatenate);
+        check_round_trip(&Subtract);
+        check_round_trip(&Divide);
+        check_round_trip(&Power);
+    }
+
+    #[test]
+    fn test_unapply() {
+        assert_eq!(Concatenate.unapply(&156_i64, &6), Preimage::One(15));
+        assert_eq!(Concatenate.unapply(&156_i64, &7), Preimage::None);
+        assert_eq!(Concatenate.unapply(&120_i64, &0), Preimage::One(12));
+        assert_eq!(Multiply.unapply(&3267_i64, &27), Preimage::One(121));
+        assert_eq!(Multiply.unapply(&3267_i64, &40), Preimage::None);
+        assert_eq!(Power.unapply(&81_i64, &4), Preimage::Two(3, -3));
+        assert_eq!(Power.unapply(&-27_i64, &3), Preimage::One(-3));
+        assert_eq!(Power.unapply(&1_i64, &0), Preimage::Any);
+    }
+
+    #[test]
+    fn test_concatenate() {
+        assert_eq!(concatenate(&12_i64, &0), Some(120));
+        assert_eq!(concatenate(&12_i64, &345), Some(12345));
+        assert_eq!(concatenate(&i64::MAX, &1), None);
+        assert_eq!(concatenate(&922_337_203_i64, &6_854_775_808), None);
+    }
+
+    #[test]
+    fn test_overflow_prunes() {
+        assert_eq!(Multiply.apply(&i64::MAX, &2), None);
+        assert_eq!(Add.apply(&i64::MAX, &1), None);
+        assert_eq!(Divide.unapply(&i64::MAX, &2), Preimage::None);
+        assert_eq!(Power.apply(&10_i64, &19), None);
+        // a huge exponent is refused instead of being worked out
+        let huge = num_bigint::BigInt::from(4_000_000_000_u32);
+        assert_eq!(Power.apply(&num_bigint::BigInt::from(7), &huge), None);
+    }
+}
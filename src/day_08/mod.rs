@@ -1,2 +1,353 @@
-pub mod part1;
-pub mod part2;
+pub mod resonance;
+use std::{fmt, ops};
+
+use anyhow::{Ok, Result};
+use gcd::Gcd;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+pub use resonance::{LineMode, Rule};
+
+// (y, x)
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Point(i64, i64);
+
+impl ops::Sub<&Point> for &Point {
+    type Output = Point;
+    fn sub(self, other: &Point) -> Point {
+        Point(self.0 - other.0, self.1 - other.1)
+    }
+}
+
+impl ops::Add<&Point> for &Point {
+    type Output = Point;
+    fn add(self, other: &Point) -> Point {
+        Point(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+impl Point {
+    // smallest step between grid points in the same direction
+    fn reduced(&self) -> Point {
+        let divisor = self.0.unsigned_abs().gcd(self.1.unsigned_abs()).max(1) as i64;
+        Point(self.0 / divisor, self.1 / divisor)
+    }
+}
+
+struct SignalMap {
+    // every row padded to `width`
+    board: Vec<Vec<char>>,
+    antennas: FxHashMap<char, Vec<Point>>,
+    width: i64,
+    height: i64,
+}
+
+impl SignalMap {
+    fn contains(&self, point: &Point) -> bool {
+        let &Point(y, x) = point;
+        (0..self.height).contains(&y) && (0..self.width).contains(&x)
+    }
+
+    // antinodes per frequency, frequencies in order
+    fn antinodes(&self, rule: &Rule) -> Result<Vec<(char, FxHashSet<Point>)>> {
+        rule.validate()?;
+        let mut frequencies: Vec<char> = self.antennas.keys().cloned().collect();
+        frequencies.sort();
+        Ok(frequencies
+            .into_iter()
+            .map(|frequency| {
+                let antena_points = &self.antennas[&frequency];
+                let mut found = FxHashSet::default();
+                for i in 0..antena_points.len() {
+                    for j in (i + 1)..antena_points.len() {
+                        found.extend(rule.antinodes(self, &antena_points[i], &antena_points[j]));
+                    }
+                }
+                (frequency, found)
+            })
+            .collect())
+    }
+
+    // antinodes drawn as '#' over whatever is there, antennas included
+    fn render(&self, antinodes: &[(char, FxHashSet<Point>)]) -> String {
+        let mut board = self.board.clone();
+        for &Point(y, x) in antinodes.iter().flat_map(|(_, found)| found) {
+            board[y as usize][x as usize] = '#';
+        }
+        board
+            .iter()
+            .map(String::from_iter)
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AntinodeReport {
+    // distinct locations over all frequencies
+    pub total: usize,
+    pub per_frequency: Vec<(char, usize)>,
+    // locations shared by at least two frequencies
+    pub overlap: usize,
+    // locations shared by each pair of frequencies, pairs without any left out
+    pub shared: Vec<((char, char), usize)>,
+}
+
+impl AntinodeReport {
+    fn new(antinodes: &[(char, FxHashSet<Point>)]) -> AntinodeReport {
+        let mut frequencies_at: FxHashMap<&Point, usize> = FxHashMap::default();
+        for found in antinodes.iter().map(|(_, found)| found) {
+            for point in found {
+                *frequencies_at.entry(point).or_default() += 1;
+            }
+        }
+        let mut shared = Vec::new();
+        for i in 0..antinodes.len() {
+            for j in (i + 1)..antinodes.len() {
+                let count = antinodes[i].1.intersection(&antinodes[j].1).count();
+                if count > 0 {
+                    shared.push(((antinodes[i].0, antinodes[j].0), count));
+                }
+            }
+        }
+        AntinodeReport {
+            total: frequencies_at.len(),
+            per_frequency: antinodes
+                .iter()
+                .map(|(frequency, found)| (*frequency, found.len()))
+                .collect(),
+            overlap: frequencies_at.values().filter(|count| **count > 1).count(),
+            shared,
+        }
+    }
+}
+
+impl fmt::Debug for SignalMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pretty_board: String = self
+            .board
+            .iter()
+            .map(String::from_iter)
+            .collect::<Vec<String>>()
+            .join("\n");
+        f.write_str("-------\n")?;
+        f.write_str(&pretty_board)?;
+        f.write_str("\n")?;
+        f.write_str("\n")
+    }
+}
+
+// The map is as wide as its longest line, shorter lines are empty past their end and trailing
+// blank lines are not part of it.
+fn parse(input: &str) -> Result<SignalMap> {
+    let mut lines: Vec<&str> = input.lines().map(str::trim_end).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut freq_map = FxHashMap::default();
+    let board = lines
+        .iter()
+        .enumerate()
+        .map(|(yindex, line)| {
+            let mut row: Vec<char> = line.chars().collect();
+            for (xindex, character) in row.iter().enumerate() {
+                if *character != '.' {
+                    freq_map
+                        .entry(*character)
+                        .or_insert(Vec::new())
+                        .push(Point(yindex as i64, xindex as i64))
+                }
+            }
+            row.resize(width, '.');
+            row
+        })
+        .collect();
+
+    Ok(SignalMap {
+        board,
+        antennas: freq_map,
+        width: width as i64,
+        height: lines.len() as i64,
+    })
+}
+
+pub fn antinodes(input: &str, rule: &Rule) -> Result<AntinodeReport> {
+    let map = parse(input)?;
+    Ok(AntinodeReport::new(&map.antinodes(rule)?))
+}
+
+pub fn render(input: &str, rule: &Rule) -> Result<String> {
+    let map = parse(input)?;
+    Ok(map.render(&map.antinodes(rule)?))
+}
+
+pub fn part1(input: &str) -> Result<usize> {
+    Ok(antinodes(input, &Rule::part1())?.total)
+}
+
+pub fn part2(input: &str) -> Result<usize> {
+    Ok(antinodes(input, &Rule::part2())?.total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounds() {
+        let map = parse("....\n....\n").unwrap();
+        assert_eq!((map.width, map.height), (4, 2));
+        assert!(map.contains(&Point(1, 3)));
+        assert!(!map.contains(&Point(3, 1)));
+        assert!(!map.contains(&Point(0, -1)));
+    }
+
+    #[test]
+    fn test_wide_map() {
+        let input = "..........\n...a.a....\n..........";
+        assert_eq!(part1(input).unwrap(), 2);
+        assert_eq!(part2(input).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_tall_map() {
+        let input = "...\n...\n...\n.a.\n...\n.a.\n...\n...\n...\n...";
+        assert_eq!(part1(input).unwrap(), 2);
+        assert_eq!(part2(input).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_ragged_lines() {
+        // the fifth line stops short and the map ends with blank lines, (5, 5) is still on it
+        let input = "......\n.a....\n......\n...a..\n......\n.....\n\n\n";
+        let map = parse(input).unwrap();
+        assert_eq!((map.width, map.height), (6, 6));
+        assert_eq!(part1(input).unwrap(), 1);
+        assert_eq!(part2(input).unwrap(), 3);
+    }
+
+    const EXAMPLE: &str = "............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............";
+
+    #[test]
+    fn test_p1() {
+        assert_eq!(part1(EXAMPLE).unwrap(), 14);
+    }
+
+    #[test]
+    fn test_p2() {
+        assert_eq!(part2(EXAMPLE).unwrap(), 34);
+    }
+
+    #[test]
+    fn test_line_modes() {
+        // the antennas are two rows and four columns apart, so (1, 2) lies between them
+        let input = "a....\n.....\n....a\n.....\n.....";
+        let rule = |mode| Rule::Harmonics {
+            mode,
+            max_harmonics: None,
+        };
+        assert_eq!(
+            antinodes(input, &rule(LineMode::PuzzleLiteral))
+                .unwrap()
+                .total,
+            2
+        );
+        assert_eq!(
+            antinodes(input, &rule(LineMode::Geometric)).unwrap().total,
+            3
+        );
+
+        let input = "a.....\n......\n..a...\n......\n......\n......";
+        assert_eq!(
+            antinodes(input, &rule(LineMode::PuzzleLiteral))
+                .unwrap()
+                .total,
+            3
+        );
+        assert_eq!(
+            antinodes(input, &rule(LineMode::Geometric)).unwrap().total,
+            6
+        );
+    }
+
+    #[test]
+    fn test_max_harmonics() {
+        let input = "a.a......";
+        let rule = |max_harmonics| Rule::Harmonics {
+            mode: LineMode::Geometric,
+            max_harmonics,
+        };
+        // the antennas, the cell between them and then one cell per harmonic past a and b
+        assert_eq!(antinodes(input, &rule(Some(0))).unwrap().total, 3);
+        assert_eq!(antinodes(input, &rule(Some(2))).unwrap().total, 5);
+        assert_eq!(antinodes(input, &rule(None)).unwrap().total, 9);
+        let rule = Rule::Harmonics {
+            mode: LineMode::PuzzleLiteral,
+            max_harmonics: Some(1),
+        };
+        assert_eq!(antinodes(input, &rule).unwrap().total, 3);
+    }
+
+    #[test]
+    fn test_ratios() {
+        let input = "...........\n...a..a....\n...........";
+        let total = |ratios: &[(i64, i64)], between| {
+            let rule = Rule::Ratios {
+                ratios: ratios.to_vec(),
+                between,
+            };
+            antinodes(input, &rule).map(|report| report.total)
+        };
+        // 2:1 puts antinodes 3 past either antenna, 4:1 one past, 3:1 would be 1.5 past
+        assert_eq!(total(&[(2, 1)], false).unwrap(), 2);
+        assert_eq!(total(&[(4, 1)], false).unwrap(), 2);
+        assert_eq!(total(&[(3, 1)], false).unwrap(), 0);
+        assert_eq!(total(&[(2, 1), (4, 1)], false).unwrap(), 4);
+        // between the antennas 2:1 splits the segment after one and after two cells
+        assert_eq!(total(&[(2, 1)], true).unwrap(), 4);
+        assert_eq!(total(&[(1, 1)], true).unwrap(), 0);
+        assert!(total(&[(1, 2)], false).is_err());
+    }
+
+    #[test]
+    fn test_report() {
+        let report = antinodes(EXAMPLE, &Rule::part1()).unwrap();
+        assert_eq!(report.total, 14);
+        assert_eq!(report.per_frequency, [('0', 10), ('A', 5)]);
+        assert_eq!(report.overlap, 1);
+        assert_eq!(report.shared, [(('0', 'A'), 1)]);
+    }
+
+    #[test]
+    fn test_render() {
+        let expected = "......#....#
+...#....0...
+....#0....#.
+..#....0....
+....0....#..
+.#....#.....
+...#........
+#......#....
+........A...
+.........A..
+..........#.
+..........#.";
+        assert_eq!(render(EXAMPLE, &Rule::part1()).unwrap(), expected);
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{ensure, Result};
+
+use super::{Point, SignalMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineMode {
+    // repeat the distance between the two antennas, as the puzzle describes it
+    #[default]
+    PuzzleLiteral,
+    // every grid point on the line through both antennas, the distance reduced by its gcd
+    Geometric,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    // Points on the line through a pair where one antenna is `far / near` times as far away as
+    // the other. Only the points outside the pair unless `between` is set, where the ratio also
+    // splits the segment itself.
+    Ratios {
+        ratios: Vec<(i64, i64)>,
+        between: bool,
+    },
+    // Points repeating along the line, the antennas included. `max_harmonics` limits how many
+    // steps past the nearer antenna count.
+    Harmonics {
+        mode: LineMode,
+        max_harmonics: Option<usize>,
+    },
+}
+
+impl Rule {
+    pub fn part1() -> Rule {
+        Rule::Ratios {
+            ratios: vec![(2, 1)],
+            between: false,
+        }
+    }
+
+    pub fn part2() -> Rule {
+        Rule::Harmonics {
+            mode: LineMode::PuzzleLiteral,
+            max_harmonics: None,
+        }
+    }
+
+    pub(super) fn validate(&self) -> Result<()> {
+        if let Rule::Ratios { ratios, .. } = self {
+            for &(far, near) in ratios {
+                ensure!(
+                    near > 0 && far >= near,
+                    "ratio {far}:{near} has to have far >= near > 0"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // antinodes of one pair of antennas that are on the map
+    pub(super) fn antinodes(&self, map: &SignalMap, a: &Point, b: &Point) -> Vec<Point> {
+        match self {
+            Rule::Ratios { ratios, between } => {
+                let mut found = Vec::new();
+                for &(far, near) in ratios {
+                    found.extend(ratio_points(a, b, far, near, *between));
+                }
+                found.retain(|point| map.contains(point));
+                found
+            }
+            Rule::Harmonics {
+                mode,
+                max_harmonics,
+            } => harmonic_points(map, a, b, *mode, *max_harmonics),
+        }
+    }
+}
+
+// `base + offset * numerator / denominator` when that lands on a grid point
+fn fraction_of(base: &Point, offset: &Point, numerator: i64, denominator: i64) -> Option<Point> {
+    let y = offset.0 * numerator;
+    let x = offset.1 * numerator;
+    if y % denominator != 0 || x % denominator != 0 {
+        return None;
+    }
+    Some(base + &Point(y / denominator, x / denominator))
+}
+
+fn ratio_points(a: &Point, b: &Point, far: i64, near: i64, between: bool) -> Vec<Point> {
+    let distance = a - b;
+    let mut found = Vec::new();
+    // past a, |P - b| = far / near * |P - a| puts P at a + distance * near / (far - near)
+    if far != near {
+        found.extend(fraction_of(a, &distance, near, far - near));
+        found.extend(fraction_of(b, &distance, -near, far - near));
+    }
+    if between {
+        found.extend(fraction_of(b, &distance, near, far + near));
+        found.extend(fraction_of(b, &distance, far, far + near));
+    }
+    found.dedup();
+    found
+}
+
+fn harmonic_points(
+    map: &SignalMap,
+    a: &Point,
+    b: &Point,
+    mode: LineMode,
+    max_harmonics: Option<usize>,
+) -> Vec<Point> {
+    let distance = a - b;
+    let (step, steps_between) = match mode {
+        LineMode::PuzzleLiteral => (distance, 1),
+        LineMode::Geometric => {
+            let step = distance.reduced();
+            let steps_between = if step.0 != 0 {
+                distance.0 / step.0
+            } else {
+                distance.1 / step.1
+            };
+            (step, steps_between)
+        }
+    };
+    // a + k * step; k >= 0 is past a, k <= -steps_between past b and anything else is between
+    let harmonic = |k: i64| {
+        if k >= 0 {
+            k
+        } else {
+            (-k - steps_between).max(0)
+        }
+    };
+    let allowed = |k: i64| max_harmonics.is_none_or(|max| harmonic(k) as usize <= max);
+
+    let mut found = Vec::new();
+    for direction in [1, -1] {
+        let mut k = if direction == 1 { 0 } else { -1 };
+        loop {
+            let point = &Point(a.0 + k * step.0, a.1 + k * step.1);
+            if !map.contains(point) {
+                break;
+            }
+            // harmonics only grow going outwards, the points between the antennas are all 0
+            if !allowed(k) {
+                break;
+            }
+            found.push(point.clone());
+            k += direction;
+        }
+    }
+    found
+}
@@ -0,0 +1,296 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+use super::{DiskMap, Extent, Layout};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveUnit {
+    // one block at a time, files get split up (part 1)
+    Block,
+    // whole files or nothing (part 2)
+    File,
+}
+
+// which free span a file goes to, always one left of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strategy {
+    // the leftmost span that fits, as in the puzzle
+    #[default]
+    FirstFit,
+    // the shortest span that fits
+    BestFit,
+    // the longest span
+    WorstFit,
+    // the first span that fits after where the previous move ended, wrapping around to the start
+    NextFit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub file_id: usize,
+    pub from: usize,
+    pub to: usize,
+    pub length: usize,
+}
+
+#[derive(Debug)]
+pub struct Compaction {
+    pub layout: Layout,
+    // in the order they happened
+    pub moves: Vec<Move>,
+}
+
+// Segment tree over `0..len`, every node `combine` of the values below it.
+struct Tree {
+    leaves: usize,
+    nodes: Vec<usize>,
+    combine: fn(usize, usize) -> usize,
+}
+
+impl Tree {
+    fn new(len: usize, combine: fn(usize, usize) -> usize, empty: usize) -> Tree {
+        let leaves = len.next_power_of_two();
+        Tree {
+            leaves,
+            nodes: vec![empty; 2 * leaves],
+            combine,
+        }
+    }
+
+    fn get(&self, index: usize) -> usize {
+        self.nodes[self.leaves + index]
+    }
+
+    fn set(&mut self, index: usize, value: usize) {
+        let mut node = self.leaves + index;
+        self.nodes[node] = value;
+        while node > 1 {
+            node /= 2;
+            self.nodes[node] = (self.combine)(self.nodes[2 * node], self.nodes[2 * node + 1]);
+        }
+    }
+
+    // leftmost index in `from..to` whose value passes, or the rightmost with `last`. `passes`
+    // has to hold for a node exactly when it holds for some value below it.
+    fn find(
+        &self,
+        from: usize,
+        to: usize,
+        last: bool,
+        passes: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        self.descend(1, 0..self.leaves, &(from..to), last, &passes)
+    }
+
+    fn descend(
+        &self,
+        node: usize,
+        covers: Range<usize>,
+        wanted: &Range<usize>,
+        last: bool,
+        passes: &impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        if covers.end <= wanted.start || wanted.end <= covers.start || !passes(self.nodes[node]) {
+            return None;
+        }
+        if covers.len() == 1 {
+            return Some(covers.start);
+        }
+        let middle = (covers.start + covers.end) / 2;
+        let left = (2 * node, covers.start..middle);
+        let right = (2 * node + 1, middle..covers.end);
+        let (first, second) = if last { (right, left) } else { (left, right) };
+        self.descend(first.0, first.1, wanted, last, passes)
+            .or_else(|| self.descend(second.0, second.1, wanted, last, passes))
+    }
+}
+
+// Free spans indexed two ways. A max tree over start positions finds the leftmost span with
+// room from some position on, for first- and next-fit. A min tree over lengths holding the
+// leftmost start of every length finds the shortest or longest length with a span left of the
+// file, for best- and worst-fit. Either way a take is logarithmic, however long the free runs
+// merged around empty files get.
+struct FreeSpans {
+    // length of the span starting at every position, 0 where none does
+    by_start: Tree,
+    // starts of the spans of every length there is one of
+    buckets: BTreeMap<usize, BTreeSet<usize>>,
+    // leftmost start of every length, usize::MAX where there is no span that long
+    by_length: Tree,
+}
+
+impl FreeSpans {
+    fn new(spans: &[(usize, usize)], size: usize) -> FreeSpans {
+        let longest = spans.iter().map(|(_, length)| *length).max().unwrap_or(0);
+        let mut free = FreeSpans {
+            by_start: Tree::new(size, usize::max, 0),
+            buckets: BTreeMap::new(),
+            by_length: Tree::new(longest + 1, usize::min, usize::MAX),
+        };
+        for &(start, length) in spans {
+            free.insert(start, length);
+        }
+        free
+    }
+
+    // spans only ever shrink, so `length` is never past the longest one `new` saw
+    fn insert(&mut self, start: usize, length: usize) {
+        if length == 0 {
+            return;
+        }
+        self.by_start.set(start, length);
+        let bucket = self.buckets.entry(length).or_default();
+        bucket.insert(start);
+        self.by_length
+            .set(length, bucket.first().copied().unwrap_or(usize::MAX));
+    }
+
+    fn remove(&mut self, start: usize, length: usize) {
+        self.by_start.set(start, 0);
+        let Some(bucket) = self.buckets.get_mut(&length) else {
+            return;
+        };
+        bucket.remove(&start);
+        self.by_length
+            .set(length, bucket.first().copied().unwrap_or(usize::MAX));
+        if bucket.is_empty() {
+            self.buckets.remove(&length);
+        }
+    }
+
+    // removes the span the strategy picks for `length` blocks among those starting before `limit`
+    fn take(
+        &mut self,
+        strategy: Strategy,
+        length: usize,
+        limit: usize,
+        cursor: usize,
+    ) -> Option<(usize, usize)> {
+        let length = length.max(1);
+        let fits = |span: usize| span >= length;
+        let left_of_file = |start: usize| start < limit;
+        let lengths = self.by_length.leaves;
+        let start = match strategy {
+            Strategy::FirstFit => self.by_start.find(0, limit, false, fits)?,
+            Strategy::NextFit => self
+                .by_start
+                .find(cursor, limit, false, fits)
+                .or_else(|| self.by_start.find(0, cursor.min(limit), false, fits))?,
+            Strategy::BestFit => {
+                let bucket = self.by_length.find(length, lengths, false, left_of_file)?;
+                self.by_length.get(bucket)
+            }
+            Strategy::WorstFit => {
+                let bucket = self.by_length.find(length, lengths, true, left_of_file)?;
+                self.by_length.get(bucket)
+            }
+        };
+        let span = self.by_start.get(start);
+        self.remove(start, span);
+        Some((start, span))
+    }
+}
+
+// Files are taken from the highest id down and only ever move left, so the space they leave
+// behind is right of everything still waiting and never needs to go back into the pool.
+pub(super) fn run(disk: &DiskMap, unit: MoveUnit, strategy: Strategy) -> Compaction {
+    let mut free = FreeSpans::new(&disk.free, disk.size);
+    // where the previous move ended, for next-fit
+    let mut cursor = 0;
+    let mut extents = Vec::with_capacity(disk.files.len());
+    let mut moves = Vec::new();
+
+    for file in disk.files.iter().rev() {
+        if file.length == 0 {
+            continue;
+        }
+        let mut remaining = file.length;
+        match unit {
+            MoveUnit::File => {
+                if let Some((start, length)) = free.take(strategy, file.length, file.start, cursor)
+                {
+                    free.insert(start + file.length, length - file.length);
+                    cursor = start + file.length;
+                    extents.push(Extent { start, ..*file });
+                    moves.push(Move {
+                        file_id: file.file_id,
+                        from: file.start,
+                        to: start,
+                        length: file.length,
+                    });
+                    remaining = 0;
+                }
+            }
+            MoveUnit::Block => {
+                while remaining > 0 {
+                    let Some((start, length)) = free.take(strategy, 1, file.start, cursor) else {
+                        break;
+                    };
+                    let moved = length.min(remaining);
+                    remaining -= moved;
+                    free.insert(start + moved, length - moved);
+                    cursor = start + moved;
+                    extents.push(Extent {
+                        file_id: file.file_id,
+                        start,
+                        length: moved,
+                    });
+                    moves.push(Move {
+                        file_id: file.file_id,
+                        from: file.start + remaining,
+                        to: start,
+                        length: moved,
+                    });
+                }
+            }
+        }
+        if remaining > 0 {
+            extents.push(Extent {
+                length: remaining,
+                ..*file
+            });
+        }
+    }
+
+    extents.sort_by_key(|extent| extent.start);
+    Compaction {
+        layout: Layout {
+            extents,
+            size: disk.size,
+        },
+        moves,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take() {
+        let spans = [(2, 3), (8, 1), (12, 3), (18, 1), (22, 4)];
+        let mut free = FreeSpans::new(&spans, 40);
+        assert_eq!(free.take(Strategy::FirstFit, 2, 40, 0), Some((2, 3)));
+        assert_eq!(free.take(Strategy::FirstFit, 2, 10, 0), None);
+        assert_eq!(free.take(Strategy::FirstFit, 1, 40, 0), Some((8, 1)));
+        free.insert(3, 2);
+        assert_eq!(free.take(Strategy::FirstFit, 2, 40, 0), Some((3, 2)));
+        assert_eq!(free.take(Strategy::FirstFit, 5, 40, 0), None);
+
+        let mut free = FreeSpans::new(&spans, 40);
+        assert_eq!(free.take(Strategy::BestFit, 1, 40, 0), Some((8, 1)));
+        assert_eq!(free.take(Strategy::BestFit, 2, 40, 0), Some((2, 3)));
+        assert_eq!(free.take(Strategy::BestFit, 4, 20, 0), None);
+
+        let mut free = FreeSpans::new(&spans, 40);
+        assert_eq!(free.take(Strategy::WorstFit, 1, 40, 0), Some((22, 4)));
+        assert_eq!(free.take(Strategy::WorstFit, 1, 20, 0), Some((2, 3)));
+
+        let mut free = FreeSpans::new(&spans, 40);
+        assert_eq!(free.take(Strategy::NextFit, 1, 40, 10), Some((12, 3)));
+        assert_eq!(free.take(Strategy::NextFit, 2, 40, 19), Some((22, 4)));
+        assert_eq!(free.take(Strategy::NextFit, 2, 40, 30), Some((2, 3)));
+    }
+}
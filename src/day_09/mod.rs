@@ -1,2 +1,302 @@
-pub mod part1;
-pub mod part2;
+pub mod compaction;
+pub mod render;
+
+use anyhow::{Context, Result};
+use rustc_hash::FxHashSet;
+
+pub use compaction::{Compaction, Move, MoveUnit, Strategy};
+pub use render::{render, trace, Style};
+
+// a file, or a piece of one once block moves split it up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    pub file_id: usize,
+    pub start: usize,
+    pub length: usize,
+}
+
+struct DiskMap {
+    files: Vec<Extent>,
+    // (start, length)
+    free: Vec<(usize, usize)>,
+    size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    // ordered by position
+    pub extents: Vec<Extent>,
+    pub size: usize,
+}
+
+impl Layout {
+    pub fn checksum(&self) -> usize {
+        self.extents
+            .iter()
+            .map(|extent| {
+                let end = extent.start + extent.length;
+                extent.file_id * (extent.start..end).sum::<usize>()
+            })
+            .sum()
+    }
+
+    // (start, length) of every maximal run of free blocks
+    pub fn free_spans(&self) -> Vec<(usize, usize)> {
+        let mut spans = Vec::new();
+        let mut end = 0;
+        for extent in &self.extents {
+            if extent.start > end {
+                spans.push((end, extent.start - end));
+            }
+            end = end.max(extent.start + extent.length);
+        }
+        if self.size > end {
+            spans.push((end, self.size - end));
+        }
+        spans
+    }
+
+    // file id of every block, None where it is free
+    pub fn blocks(&self) -> Vec<Option<usize>> {
+        let mut blocks = vec![None; self.size];
+        for extent in &self.extents {
+            for block in &mut blocks[extent.start..extent.start + extent.length] {
+                *block = Some(extent.file_id);
+            }
+        }
+        blocks
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactionReport {
+    pub checksum: usize,
+    pub moved_files: usize,
+    pub free_spans_before: usize,
+    pub free_spans_after: usize,
+    pub largest_free_span: usize,
+    // 1 - largest free span / all free space, 0 when the free space is in one piece
+    pub fragmentation: f64,
+}
+
+impl DiskMap {
+    fn layout(&self) -> Layout {
+        Layout {
+            extents: self.files.clone(),
+            size: self.size,
+        }
+    }
+}
+
+fn fragmentation(spans: &[(usize, usize)]) -> f64 {
+    let total: usize = spans.iter().map(|(_, length)| length).sum();
+    let largest = spans.iter().map(|(_, length)| *length).max().unwrap_or(0);
+    if total == 0 {
+        return 0.0;
+    }
+    1.0 - largest as f64 / total as f64
+}
+
+fn parse(input: &str) -> Result<DiskMap> {
+    let mut disk = DiskMap {
+        files: Vec::new(),
+        free: Vec::new(),
+        size: 0,
+    };
+    for (index, charmander) in input.trim().chars().enumerate() {
+        let length = charmander
+            .to_digit(10)
+            .with_context(|| format!("Failed to parse char: {}", charmander))?
+            as usize;
+        if index % 2 == 0 {
+            disk.files.push(Extent {
+                file_id: index / 2,
+                start: disk.size,
+                length,
+            });
+        } else {
+            // an empty file in between doesn't split the free space around it
+            match disk.free.last_mut() {
+                Some((start, free)) if *start + *free == disk.size => *free += length,
+                _ => disk.free.push((disk.size, length)),
+            }
+        }
+        disk.size += length;
+    }
+    Ok(disk)
+}
+
+pub fn compact(input: &str, unit: MoveUnit, strategy: Strategy) -> Result<Compaction> {
+    Ok(compaction::run(&parse(input)?, unit, strategy))
+}
+
+pub fn report(input: &str, unit: MoveUnit, strategy: Strategy) -> Result<CompactionReport> {
+    let disk = parse(input)?;
+    let compaction = compaction::run(&disk, unit, strategy);
+    let after = compaction.layout.free_spans();
+    let moved: FxHashSet<usize> = compaction.moves.iter().map(|step| step.file_id).collect();
+    Ok(CompactionReport {
+        checksum: compaction.layout.checksum(),
+        moved_files: moved.len(),
+        free_spans_before: disk.layout().free_spans().len(),
+        free_spans_after: after.len(),
+        largest_free_span: after.iter().map(|(_, length)| *length).max().unwrap_or(0),
+        fragmentation: fragmentation(&after),
+    })
+}
+
+// every strategy with both move units on the same disk map
+pub fn compare(input: &str) -> Result<Vec<(MoveUnit, Strategy, CompactionReport)>> {
+    let mut reports = Vec::new();
+    for unit in [MoveUnit::Block, MoveUnit::File] {
+        for strategy in [
+            Strategy::FirstFit,
+            Strategy::BestFit,
+            Strategy::WorstFit,
+            Strategy::NextFit,
+        ] {
+            reports.push((unit, strategy, report(input, unit, strategy)?));
+        }
+    }
+    Ok(reports)
+}
+
+pub fn part1(input: &str) -> Result<usize> {
+    Ok(compact(input, MoveUnit::Block, Strategy::FirstFit)?
+        .layout
+        .checksum())
+}
+
+pub fn part2(input: &str) -> Result<usize> {
+    Ok(compact(input, MoveUnit::File, Strategy::FirstFit)?
+        .layout
+        .checksum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(layout: &Layout) -> String {
+        render(layout, Style::Base62).unwrap()
+    }
+
+    #[test]
+    fn test_p1() {
+        let input = "2333133121414131402";
+        let result = part1(input);
+        assert_eq!(result.unwrap(), 1928);
+    }
+
+    #[test]
+    fn test_defragging() {
+        let input = "2333133121414131402";
+        let result = compact(input, MoveUnit::Block, Strategy::FirstFit).unwrap();
+        assert_eq!(
+            ids(&result.layout),
+            "0099811188827773336446555566.............."
+        );
+    }
+
+    #[test]
+    fn test_very_simple_defragging() {
+        let input = "12345";
+        let result = compact(input, MoveUnit::Block, Strategy::FirstFit).unwrap();
+        assert_eq!(ids(&result.layout), "022111222......");
+        assert_eq!(result.moves.len(), 2);
+    }
+
+    #[test]
+    fn test_p2() {
+        let input = "2333133121414131402";
+        let result = part2(input);
+        assert_eq!(result.unwrap(), 2858);
+    }
+
+    #[test]
+    fn test_whole_files() {
+        let input = "2333133121414131402";
+        let result = compact(input, MoveUnit::File, Strategy::FirstFit).unwrap();
+        assert_eq!(
+            ids(&result.layout),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
+        let moved: Vec<usize> = result.moves.iter().map(|step| step.file_id).collect();
+        assert_eq!(moved, [9, 7, 4, 2]);
+    }
+
+    #[test]
+    fn test_12345() {
+        let input = "54321";
+        let result = part2(input);
+        assert_eq!(result.unwrap(), 31);
+    }
+
+    #[test]
+    fn test_empty_file() {
+        // file 1 is empty, so the two free blocks around it are one span that fits file 2
+        assert_eq!(part2("11012").unwrap(), 2 + 2 * 2);
+    }
+
+    #[test]
+    fn test_report() {
+        let input = "2333133121414131402";
+        let result = report(input, MoveUnit::File, Strategy::FirstFit).unwrap();
+        assert_eq!(result.checksum, 2858);
+        assert_eq!(result.moved_files, 4);
+        assert_eq!(result.free_spans_before, 8);
+        assert_eq!(result.free_spans_after, 6);
+        assert_eq!(result.largest_free_span, 5);
+        assert!((result.fragmentation - (1.0 - 5.0 / 14.0)).abs() < 1e-9);
+
+        let result = report(input, MoveUnit::Block, Strategy::FirstFit).unwrap();
+        assert_eq!(result.checksum, 1928);
+        assert_eq!(result.free_spans_after, 1);
+        assert_eq!(result.fragmentation, 0.0);
+    }
+
+    #[test]
+    fn test_strategies() {
+        let input = "1312231";
+        let first = compact(input, MoveUnit::File, Strategy::FirstFit).unwrap();
+        assert_eq!(ids(&first.layout), "03221........");
+        let best = compact(input, MoveUnit::File, Strategy::BestFit).unwrap();
+        assert_eq!(ids(&best.layout), "0221.3.......");
+
+        // next-fit keeps going right of the previous move before it wraps around
+        let input = "111312103";
+        let first = compact(input, MoveUnit::File, Strategy::FirstFit).unwrap();
+        assert_eq!(ids(&first.layout), "0314442......");
+        let next = compact(input, MoveUnit::File, Strategy::NextFit).unwrap();
+        assert_eq!(ids(&next.layout), "021444.3.....");
+    }
+
+    #[test]
+    fn test_next_fit_only_moves_left() {
+        // the cursor ends up right of file 1, wrapping around mustn't go past the file either
+        let input = "1011222";
+        for unit in [MoveUnit::Block, MoveUnit::File] {
+            let result = compact(input, unit, Strategy::NextFit).unwrap();
+            assert!(result.moves.iter().all(|step| step.to < step.from));
+        }
+        let result = compact(input, MoveUnit::File, Strategy::NextFit).unwrap();
+        assert_eq!(ids(&result.layout), "01.2233..");
+    }
+
+    #[test]
+    fn test_compare() {
+        let reports = compare("2333133121414131402").unwrap();
+        assert_eq!(reports.len(), 8);
+        let checksums: Vec<usize> = reports
+            .iter()
+            .filter(|(_, strategy, _)| *strategy == Strategy::FirstFit)
+            .map(|(_, _, result)| result.checksum)
+            .collect();
+        assert_eq!(checksums, [1928, 2858]);
+    }
+
+    #[test]
+    fn test_bad_input() {
+        assert!(part1("12a4").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+
+use super::{parse, Compaction, Layout, MoveUnit, Strategy};
+
+const BASE62: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    // one character per block, ids past 61 can't be drawn
+    Base62,
+    // one coloured square per block, the colours repeat every 216 ids
+    Colour,
+    // `id*length` for every run of blocks, `.` for free ones
+    RunLength,
+}
+
+fn glyph(out: &mut String, block: Option<usize>, style: Style) -> Result<()> {
+    match (block, style) {
+        (None, _) => out.push('.'),
+        (Some(id), Style::Base62) => match BASE62.get(id) {
+            Some(&glyph) => out.push(glyph as char),
+            None => bail!("file id {id} has no base-62 glyph"),
+        },
+        (Some(id), _) => write!(out, "\x1b[38;5;{}m█\x1b[0m", 16 + id % 216)?,
+    }
+    Ok(())
+}
+
+fn render_blocks(blocks: &[Option<usize>], style: Style) -> Result<String> {
+    let mut out = String::new();
+    if style != Style::RunLength {
+        for &block in blocks {
+            glyph(&mut out, block, style)?;
+        }
+        return Ok(out);
+    }
+    for run in blocks.chunk_by(|a, b| a == b) {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        match run[0] {
+            Some(id) => write!(out, "{id}*{}", run.len())?,
+            None => write!(out, ".*{}", run.len())?,
+        }
+    }
+    Ok(out)
+}
+
+fn relocate(blocks: &mut [Option<usize>], from: usize, to: usize) {
+    blocks[to] = blocks[from].take();
+}
+
+pub fn render(layout: &Layout, style: Style) -> Result<String> {
+    render_blocks(&layout.blocks(), style)
+}
+
+// The disk before compaction and after every move. Block moves are replayed one block at a
+// time, the last block of the file first, the way the puzzle draws them.
+pub fn trace(input: &str, unit: MoveUnit, strategy: Strategy, style: Style) -> Result<Vec<String>> {
+    let disk = parse(input)?;
+    let Compaction { moves, .. } = super::compaction::run(&disk, unit, strategy);
+    let mut blocks = disk.layout().blocks();
+    let mut frames = vec![render_blocks(&blocks, style)?];
+    for step in moves {
+        match unit {
+            MoveUnit::Block => {
+                for offset in 0..step.length {
+                    relocate(
+                        &mut blocks,
+                        step.from + step.length - 1 - offset,
+                        step.to + offset,
+                    );
+                    frames.push(render_blocks(&blocks, style)?);
+                }
+            }
+            MoveUnit::File => {
+                for offset in 0..step.length {
+                    relocate(&mut blocks, step.from + offset, step.to + offset);
+                }
+                frames.push(render_blocks(&blocks, style)?);
+            }
+        }
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_blocks() {
+        let frames = trace("12345", MoveUnit::Block, Strategy::FirstFit, Style::Base62).unwrap();
+        assert_eq!(
+            frames,
+            [
+                "0..111....22222",
+                "02.111....2222.",
+                "022111....222..",
+                "0221112...22...",
+                "02211122..2....",
+                "022111222......",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_files() {
+        let input = "2333133121414131402";
+        let frames = trace(input, MoveUnit::File, Strategy::FirstFit, Style::Base62).unwrap();
+        assert_eq!(
+            frames,
+            [
+                "00...111...2...333.44.5555.6666.777.888899",
+                "0099.111...2...333.44.5555.6666.777.8888..",
+                "0099.1117772...333.44.5555.6666.....8888..",
+                "0099.111777244.333....5555.6666.....8888..",
+                "00992111777.44.333....5555.6666.....8888..",
+            ]
+        );
+        let frames = trace(input, MoveUnit::Block, Strategy::FirstFit, Style::Base62).unwrap();
+        assert_eq!(frames[1], "009..111...2...333.44.5555.6666.777.88889.");
+        assert_eq!(
+            frames.last().unwrap(),
+            "0099811188827773336446555566.............."
+        );
+    }
+
+    #[test]
+    fn test_multi_digit_ids() {
+        // files 10 and 11 are one glyph each, where their ids alone would be two digits
+        let input = "10101010101010101010101";
+        let frames = trace(input, MoveUnit::File, Strategy::FirstFit, Style::Base62).unwrap();
+        assert_eq!(frames[0], "0123456789ab");
+        let frames = trace(input, MoveUnit::File, Strategy::FirstFit, Style::RunLength).unwrap();
+        assert_eq!(
+            frames[0],
+            "0*1 1*1 2*1 3*1 4*1 5*1 6*1 7*1 8*1 9*1 10*1 11*1"
+        );
+
+        let mut input = "10".repeat(62);
+        input.push('1');
+        let frames = trace(&input, MoveUnit::File, Strategy::FirstFit, Style::Base62);
+        assert!(frames.is_err());
+    }
+
+    #[test]
+    fn test_run_length() {
+        let input = "2333133121414131402";
+        let frames = trace(input, MoveUnit::File, Strategy::FirstFit, Style::RunLength).unwrap();
+        assert_eq!(
+            frames.last().unwrap(),
+            "0*2 9*2 2*1 1*3 7*3 .*1 4*2 .*1 3*3 .*4 5*4 .*1 6*4 .*5 8*4 .*2"
+        );
+    }
+}
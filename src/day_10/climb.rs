@@ -0,0 +1,159 @@
+use anyhow::{bail, ensure, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    // the next height is exactly this much higher, negative to go down instead
+    Exact(i64),
+    // the next height is between `min` and `max` higher
+    Range { min: i64, max: i64 },
+    // up or down by between `min` and `max`
+    AllowDescent { min: i64, max: i64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heights {
+    // one character per spot, digits and then letters up to the radix
+    Chars { radix: u32 },
+    // whitespace separated numbers, for heights any size
+    Separated,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClimbRules {
+    pub step: Step,
+    pub start: i64,
+    pub goal: i64,
+    // spots nobody can stand on, anything else that isn't a height is an error
+    pub impassable: Vec<char>,
+    pub heights: Heights,
+}
+
+impl Default for ClimbRules {
+    // the puzzle's: up exactly 1 from 0 to 9, '.' can't be walked on
+    fn default() -> ClimbRules {
+        ClimbRules {
+            step: Step::Exact(1),
+            start: 0,
+            goal: 9,
+            impassable: vec!['.'],
+            heights: Heights::Chars { radix: 10 },
+        }
+    }
+}
+
+impl ClimbRules {
+    pub(super) fn allows(&self, from: i64, to: i64) -> bool {
+        let rise = to - from;
+        match self.step {
+            Step::Exact(step) => rise == step,
+            Step::Range { min, max } => (min..=max).contains(&rise),
+            Step::AllowDescent { min, max } => (min..=max).contains(&rise.abs()),
+        }
+    }
+
+    fn marker(&self, token: &str) -> bool {
+        let mut chars = token.chars();
+        match (chars.next(), chars.next()) {
+            (Some(marker), None) => self.impassable.contains(&marker),
+            _ => false,
+        }
+    }
+
+    // every row padded to the widest with impassable spots
+    pub(super) fn parse(&self, input: &str) -> Result<(Vec<Option<i64>>, usize)> {
+        let mut rows: Vec<Vec<Option<i64>>> = Vec::new();
+        for (y, line) in input.lines().map(str::trim_end).enumerate() {
+            let tokens: Vec<String> = match self.heights {
+                Heights::Chars { radix } => {
+                    ensure!((2..=36).contains(&radix), "radix {radix} is not in 2..=36");
+                    line.chars().map(String::from).collect()
+                }
+                Heights::Separated => line.split_whitespace().map(String::from).collect(),
+            };
+            let mut row = Vec::with_capacity(tokens.len());
+            for (x, token) in tokens.iter().enumerate() {
+                if self.marker(token) {
+                    row.push(None);
+                    continue;
+                }
+                let height = match self.heights {
+                    Heights::Chars { radix } => token
+                        .chars()
+                        .next()
+                        .and_then(|charmander| charmander.to_digit(radix))
+                        .map(i64::from),
+                    Heights::Separated => token.parse().ok(),
+                };
+                match height {
+                    Some(height) => row.push(Some(height)),
+                    None => bail!("{token:?} at ({x}, {y}) is neither a height nor impassable"),
+                }
+            }
+            rows.push(row);
+        }
+        // trailing blank lines aren't part of the map
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let heights = rows
+            .into_iter()
+            .flat_map(|mut row| {
+                row.resize(width, None);
+                row
+            })
+            .collect();
+        Ok((heights, width))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows() {
+        let puzzle = ClimbRules::default();
+        assert!(puzzle.allows(3, 4));
+        assert!(!puzzle.allows(3, 5));
+        assert!(!puzzle.allows(4, 3));
+        let range = ClimbRules {
+            step: Step::Range { min: 0, max: 2 },
+            ..ClimbRules::default()
+        };
+        assert!(range.allows(3, 3) && range.allows(3, 5) && !range.allows(3, 2));
+        let descent = ClimbRules {
+            step: Step::AllowDescent { min: 1, max: 1 },
+            ..ClimbRules::default()
+        };
+        assert!(descent.allows(3, 2) && descent.allows(3, 4) && !descent.allows(3, 3));
+    }
+
+    #[test]
+    fn test_parse() {
+        let rules = ClimbRules::default();
+        let (heights, width) = rules.parse("0.1\n23\n\n").unwrap();
+        assert_eq!(width, 3);
+        assert_eq!(heights, [Some(0), None, Some(1), Some(2), Some(3), None]);
+        assert!(rules.parse("0#1").is_err());
+
+        let rules = ClimbRules {
+            heights: Heights::Separated,
+            impassable: vec!['#'],
+            ..ClimbRules::default()
+        };
+        let (heights, width) = rules.parse("10 # 12\n-3 100").unwrap();
+        assert_eq!(width, 3);
+        assert_eq!(
+            heights,
+            [Some(10), None, Some(12), Some(-3), Some(100), None]
+        );
+        assert!(rules.parse("1 . 2").is_err());
+
+        let rules = ClimbRules {
+            heights: Heights::Chars { radix: 36 },
+            ..ClimbRules::default()
+        };
+        assert_eq!(rules.parse("9aZ").unwrap().0, [Some(9), Some(10), Some(35)]);
+    }
+}
@@ -1,2 +1,496 @@
-pub mod part1;
-pub mod part2;
+pub mod climb;
+pub mod trails;
+
+use anyhow::{Context, Result};
+
+pub use climb::{ClimbRules, Heights, Step};
+pub use trails::Trails;
+
+// (x, y)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point(pub usize, pub usize);
+
+// which peaks a spot can reach, one bit per peak
+#[derive(Debug, Clone, Default)]
+struct PeakSet(Vec<u64>);
+
+impl PeakSet {
+    fn with(peak: usize) -> PeakSet {
+        let mut words = vec![0; peak / 64 + 1];
+        words[peak / 64] |= 1 << (peak % 64);
+        PeakSet(words)
+    }
+
+    fn union_with(&mut self, other: &PeakSet) {
+        if other.0.len() > self.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word |= other;
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+// The steps the rules allow between spots, built once. Spots that can all reach each other are
+// one component, and scores and ratings are folded over the components from the peaks down, so
+// every spot is worked out a single time however many trails go through it.
+pub struct TrailMap {
+    // every row padded to `width`, None where there is no height
+    heights: Vec<Option<i64>>,
+    width: usize,
+    start: i64,
+    goal: i64,
+    // allowed steps from every spot, none out of a peak
+    edges: Vec<Vec<usize>>,
+    // component of every spot
+    component: Vec<usize>,
+    // per component
+    reach: Vec<PeakSet>,
+    // None where a trail can go around in circles, so there are infinitely many
+    ratings: Vec<Option<u64>>,
+}
+
+impl TrailMap {
+    pub fn new(input: &str) -> Result<TrailMap> {
+        TrailMap::with_rules(input, &ClimbRules::default())
+    }
+
+    pub fn with_rules(input: &str, rules: &ClimbRules) -> Result<TrailMap> {
+        let (heights, width) = rules.parse(input)?;
+        let mut map = TrailMap {
+            heights,
+            width,
+            start: rules.start,
+            goal: rules.goal,
+            edges: Vec::new(),
+            component: Vec::new(),
+            reach: Vec::new(),
+            ratings: Vec::new(),
+        };
+        map.edges = (0..map.heights.len())
+            .map(|spot| map.steps(spot, rules))
+            .collect();
+
+        let members = map.components();
+        let mut peaks = 0;
+        map.reach = map.fold(
+            &members,
+            PeakSet::default(),
+            |reach, next| {
+                reach.union_with(next);
+                Ok(())
+            },
+            |spots, mut reach| {
+                if map.is_peak(spots[0]) {
+                    reach.union_with(&PeakSet::with(peaks));
+                    peaks += 1;
+                }
+                Ok(reach)
+            },
+        )?;
+        map.ratings = map.fold(
+            &members,
+            Some(0_u64),
+            |rating, next| {
+                *rating = match (*rating, *next) {
+                    (_, Some(0)) => *rating,
+                    (Some(rating), Some(next)) => Some(
+                        rating
+                            .checked_add(next)
+                            .context("too many trails to count")?,
+                    ),
+                    _ => None,
+                };
+                Ok(())
+            },
+            |spots, rating| {
+                Ok(match rating {
+                    _ if map.is_peak(spots[0]) => Some(1),
+                    Some(0) => Some(0),
+                    _ if spots.len() > 1 => None,
+                    _ => rating,
+                })
+            },
+        )?;
+        Ok(map)
+    }
+
+    fn is_peak(&self, spot: usize) -> bool {
+        self.heights[spot] == Some(self.goal)
+    }
+
+    fn steps(&self, spot: usize, rules: &ClimbRules) -> Vec<usize> {
+        let Some(height) = self.heights[spot] else {
+            return Vec::new();
+        };
+        if self.is_peak(spot) {
+            return Vec::new();
+        }
+        let (x, y) = (spot % self.width, spot / self.width);
+        let mut next = Vec::new();
+        if x > 0 {
+            next.push(spot - 1);
+        }
+        if x + 1 < self.width {
+            next.push(spot + 1);
+        }
+        if y > 0 {
+            next.push(spot - self.width);
+        }
+        if spot + self.width < self.heights.len() {
+            next.push(spot + self.width);
+        }
+        next.retain(|&other| self.heights[other].is_some_and(|to| rules.allows(height, to)));
+        next
+    }
+
+    // Tarjan's strongly connected components, which come out with every component after all the
+    // ones it can step to. Fills in `component` and returns the spots in each.
+    fn components(&mut self) -> Vec<Vec<usize>> {
+        let size = self.heights.len();
+        let mut index = vec![usize::MAX; size];
+        let mut low = vec![0; size];
+        let mut on_stack = vec![false; size];
+        let mut stack = Vec::new();
+        let mut counter = 0;
+        self.component = vec![usize::MAX; size];
+        let mut members = Vec::new();
+
+        for root in 0..size {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            let mut calls = vec![(root, 0)];
+            index[root] = counter;
+            low[root] = counter;
+            counter += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some((spot, edge)) = calls.last_mut() {
+                let spot = *spot;
+                if let Some(&next) = self.edges[spot].get(*edge) {
+                    *edge += 1;
+                    if index[next] == usize::MAX {
+                        index[next] = counter;
+                        low[next] = counter;
+                        counter += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        calls.push((next, 0));
+                    } else if on_stack[next] {
+                        low[spot] = low[spot].min(index[next]);
+                    }
+                    continue;
+                }
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[spot]);
+                }
+                if low[spot] == index[spot] {
+                    let mut spots = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        self.component[member] = members.len();
+                        spots.push(member);
+                        if member == spot {
+                            break;
+                        }
+                    }
+                    members.push(spots);
+                }
+            }
+        }
+        members
+    }
+
+    // A value for every component: its steps out merged into `zero`, then `finish` has the last
+    // word with the spots of the component in hand.
+    fn fold<T: Clone>(
+        &self,
+        members: &[Vec<usize>],
+        zero: T,
+        merge: impl Fn(&mut T, &T) -> Result<()>,
+        mut finish: impl FnMut(&[usize], T) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut values: Vec<T> = Vec::with_capacity(members.len());
+        for (component, spots) in members.iter().enumerate() {
+            let mut value = zero.clone();
+            for &spot in spots {
+                for &next in &self.edges[spot] {
+                    if self.component[next] != component {
+                        merge(&mut value, &values[self.component[next]])?;
+                    }
+                }
+            }
+            values.push(finish(spots, value)?);
+        }
+        Ok(values)
+    }
+
+    fn spot(&self, point: Point) -> Option<usize> {
+        let Point(x, y) = point;
+        (x < self.width && y * self.width + x < self.heights.len()).then_some(y * self.width + x)
+    }
+
+    fn point(&self, spot: usize) -> Point {
+        Point(spot % self.width, spot / self.width)
+    }
+
+    fn leads_to_peak(&self, spot: usize) -> bool {
+        !self.reach[self.component[spot]].is_empty()
+    }
+
+    // in reading order
+    pub fn trailheads(&self) -> Vec<Point> {
+        (0..self.heights.len())
+            .filter(|&spot| self.heights[spot] == Some(self.start))
+            .map(|spot| self.point(spot))
+            .collect()
+    }
+
+    // how many peaks can be reached
+    pub fn score(&self, trailhead: Point) -> usize {
+        self.spot(trailhead)
+            .map_or(0, |spot| self.reach[self.component[spot]].count())
+    }
+
+    // how many distinct trails lead to a peak, None if there's no end to them
+    pub fn rating(&self, trailhead: Point) -> Option<u64> {
+        self.spot(trailhead)
+            .map_or(Some(0), |spot| self.ratings[self.component[spot]])
+    }
+
+    pub fn trails(&self, trailhead: Point) -> Trails<'_> {
+        Trails::new(self, self.spot(trailhead))
+    }
+}
+
+pub fn part1_with(input: &str, rules: &ClimbRules) -> Result<usize> {
+    let map = TrailMap::with_rules(input, rules)?;
+    Ok(map
+        .trailheads()
+        .into_iter()
+        .map(|trailhead| map.score(trailhead))
+        .sum())
+}
+
+pub fn part2_with(input: &str, rules: &ClimbRules) -> Result<u64> {
+    let map = TrailMap::with_rules(input, rules)?;
+    let mut total: u64 = 0;
+    for trailhead in map.trailheads() {
+        let rating = map
+            .rating(trailhead)
+            .context("trails can go around in circles, there is no end to them")?;
+        total = total
+            .checked_add(rating)
+            .context("too many trails to count")?;
+    }
+    Ok(total)
+}
+
+pub fn part1(input: &str) -> Result<usize> {
+    part1_with(input, &ClimbRules::default())
+}
+
+pub fn part2(input: &str) -> Result<u64> {
+    part2_with(input, &ClimbRules::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "89010123
+78121874
+87430965
+96549874
+45678903
+32019012
+01329801
+10456732";
+
+    #[test]
+    fn test_p1_simple() {
+        let input = "0123
+1234
+8765
+9876";
+        let result = part1(input);
+        assert_eq!(result.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_p1_harder() {
+        let input = "...0...
+...1...
+...2...
+6543456
+7.....7
+8.....8
+9.....9";
+        let result = part1(input);
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[test]
+    fn test_p1_simple2() {
+        let input = "..90..9
+...1.98
+...2..7
+6543456
+765.987
+876....
+987....";
+        let result = part1(input);
+        assert_eq!(result.unwrap(), 4);
+    }
+
+    #[test]
+    fn test_p1_harder2() {
+        let input = "10..9..
+2...8..
+3...7..
+4567654
+...8..3
+...9..2
+.....01";
+        let result = part1(input);
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_p1() {
+        let result = part1(EXAMPLE);
+        assert_eq!(result.unwrap(), 36);
+    }
+
+    #[test]
+    fn test_p2_simple() {
+        let input = ".....0.
+..4321.
+..5..2.
+..6543.
+..7..4.
+..8765.
+..9....";
+        let result = part2(input);
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_p2_harder() {
+        let input = "..90..9
+...1.98
+...2..7
+6543456
+765.987
+876....
+987....";
+        let result = part2(input);
+        assert_eq!(result.unwrap(), 13);
+    }
+
+    #[test]
+    fn test_p2_simple2() {
+        let input = "012345
+123456
+234567
+345678
+4.6789
+56789.";
+        let result = part2(input);
+        assert_eq!(result.unwrap(), 227);
+    }
+
+    #[test]
+    fn test_p2() {
+        let result = part2(EXAMPLE);
+        assert_eq!(result.unwrap(), 81);
+    }
+
+    #[test]
+    fn test_per_trailhead() {
+        let map = TrailMap::new(EXAMPLE).unwrap();
+        let trailheads = map.trailheads();
+        let scores: Vec<usize> = trailheads.iter().map(|&head| map.score(head)).collect();
+        assert_eq!(scores, [5, 6, 5, 3, 1, 3, 5, 3, 5]);
+        let ratings: Vec<u64> = trailheads
+            .iter()
+            .map(|&head| map.rating(head).unwrap())
+            .collect();
+        assert_eq!(ratings, [20, 24, 10, 4, 1, 4, 5, 8, 5]);
+        assert_eq!(map.score(Point(20, 20)), 0);
+    }
+
+    #[test]
+    fn test_downhill() {
+        let rules = ClimbRules {
+            step: Step::Exact(-1),
+            start: 9,
+            goal: 0,
+            ..ClimbRules::default()
+        };
+        // every trail of the puzzle walked backwards
+        let map = TrailMap::with_rules(EXAMPLE, &rules).unwrap();
+        let heads = map.trailheads();
+        let score: usize = heads.iter().map(|&head| map.score(head)).sum();
+        let rating: u64 = heads.iter().map(|&head| map.rating(head).unwrap()).sum();
+        assert_eq!((score, rating), (36, 81));
+    }
+
+    #[test]
+    fn test_high_ground() {
+        let rules = ClimbRules {
+            step: Step::Range { min: 1, max: 10 },
+            goal: 100,
+            impassable: vec!['#'],
+            heights: Heights::Separated,
+            ..ClimbRules::default()
+        };
+        let input = "0 10 20 #
+# 9 30 40
+100 # # 50
+90 80 70 60";
+        let map = TrailMap::with_rules(input, &rules).unwrap();
+        assert_eq!(map.trailheads(), [Point(0, 0)]);
+        assert_eq!(map.score(Point(0, 0)), 1);
+        assert_eq!(map.rating(Point(0, 0)), Some(1));
+        assert_eq!(map.trails(Point(0, 0)).next().unwrap().len(), 11);
+    }
+
+    #[test]
+    fn test_going_in_circles() {
+        let rules = ClimbRules {
+            step: Step::AllowDescent { min: 1, max: 1 },
+            ..ClimbRules::default()
+        };
+        let input = "0123456789\n1........8";
+        let map = TrailMap::with_rules(input, &rules).unwrap();
+        assert_eq!(map.score(Point(0, 0)), 1);
+        assert_eq!(map.rating(Point(0, 0)), None);
+        assert!(part2_with(input, &rules).is_err());
+        // trails never come back to a spot, so the only one is along the top
+        assert_eq!(map.trails(Point(0, 0)).count(), 1);
+
+        // flat steps go back and forth just the same
+        let flat = ClimbRules {
+            step: Step::Range { min: 0, max: 1 },
+            ..ClimbRules::default()
+        };
+        let map = TrailMap::with_rules("0123456789\n.....5....", &flat).unwrap();
+        assert_eq!(map.score(Point(0, 0)), 1);
+        assert_eq!(map.rating(Point(0, 0)), None);
+        // a spot that can only step onto the trail doesn't count
+        let map = TrailMap::with_rules("0123456789\n.......6..", &flat).unwrap();
+        assert_eq!(map.rating(Point(0, 0)), Some(1));
+    }
+}
@@ -0,0 +1,109 @@
+use super::{Point, TrailMap};
+
+// Every trail from one trailhead to a peak, depth first. Steps that lead to no peak are never
+// taken, so each dead end costs nothing, and with rules that let trails go around in circles
+// only the trails that never come back to a spot are listed.
+pub struct Trails<'a> {
+    map: &'a TrailMap,
+    path: Vec<usize>,
+    on_path: Vec<bool>,
+    // the next step to try out of every spot on the path
+    next_edge: Vec<usize>,
+}
+
+impl<'a> Trails<'a> {
+    pub(super) fn new(map: &'a TrailMap, trailhead: Option<usize>) -> Trails<'a> {
+        let path: Vec<usize> = trailhead
+            .filter(|&spot| map.leads_to_peak(spot))
+            .into_iter()
+            .collect();
+        let mut on_path = vec![false; map.heights.len()];
+        for &spot in &path {
+            on_path[spot] = true;
+        }
+        Trails {
+            map,
+            on_path,
+            next_edge: vec![0; path.len()],
+            path,
+        }
+    }
+}
+
+impl Trails<'_> {
+    fn back_up(&mut self) {
+        if let Some(spot) = self.path.pop() {
+            self.on_path[spot] = false;
+        }
+        self.next_edge.pop();
+    }
+}
+
+impl Iterator for Trails<'_> {
+    type Item = Vec<Point>;
+
+    fn next(&mut self) -> Option<Vec<Point>> {
+        let map = self.map;
+        loop {
+            let spot = *self.path.last()?;
+            if map.is_peak(spot) {
+                let trail = self.path.iter().map(|&spot| map.point(spot)).collect();
+                self.back_up();
+                return Some(trail);
+            }
+            let edges = &map.edges[spot];
+            let edge = self.next_edge.last_mut()?;
+            while *edge < edges.len()
+                && (self.on_path[edges[*edge]] || !map.leads_to_peak(edges[*edge]))
+            {
+                *edge += 1;
+            }
+            match edges.get(*edge) {
+                Some(&next) => {
+                    *edge += 1;
+                    self.path.push(next);
+                    self.next_edge.push(0);
+                    self.on_path[next] = true;
+                }
+                None => self.back_up(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trails() {
+        let input = ".....0.
+..4321.
+..5..2.
+..6543.
+..7..4.
+..8765.
+..9....";
+        let map = TrailMap::new(input).unwrap();
+        let trails: Vec<Vec<Point>> = map.trails(Point(5, 0)).collect();
+        assert_eq!(trails.len(), 3);
+        for trail in &trails {
+            assert_eq!(trail.len(), 10);
+            assert_eq!(trail[0], Point(5, 0));
+            assert_eq!(trail[9], Point(2, 6));
+            for step in trail.windows(2) {
+                assert_eq!(
+                    step[0].0.abs_diff(step[1].0) + step[0].1.abs_diff(step[1].1),
+                    1
+                );
+            }
+        }
+        assert_ne!(trails[0], trails[1]);
+        assert_ne!(trails[1], trails[2]);
+        assert_ne!(trails[0], trails[2]);
+
+        assert_eq!(map.trails(Point(0, 0)).count(), 0);
+        let rating = map.rating(Point(5, 0)).unwrap();
+        assert_eq!(map.trails(Point(5, 0)).count() as u64, rating);
+    }
+}
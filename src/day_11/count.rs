@@ -0,0 +1,51 @@
+use std::fmt;
+
+use num_bigint::BigUint;
+
+// How many stones there are of something. Adding up is checked, so running out of room is an
+// error instead of a count that wrapped around.
+pub trait Count: Clone + PartialEq + fmt::Debug + fmt::Display + Send + Sync + 'static {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn to_biguint(&self) -> BigUint;
+    // None when the value doesn't fit
+    fn from_biguint(value: &BigUint) -> Option<Self>;
+}
+
+impl Count for u128 {
+    fn zero() -> Self {
+        0
+    }
+    fn one() -> Self {
+        1
+    }
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+    fn to_biguint(&self) -> BigUint {
+        BigUint::from(*self)
+    }
+    fn from_biguint(value: &BigUint) -> Option<Self> {
+        u128::try_from(value).ok()
+    }
+}
+
+// never runs out of room
+impl Count for BigUint {
+    fn zero() -> Self {
+        BigUint::ZERO
+    }
+    fn one() -> Self {
+        BigUint::from(1_u8)
+    }
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+    fn to_biguint(&self) -> BigUint {
+        self.clone()
+    }
+    fn from_biguint(value: &BigUint) -> Option<Self> {
+        Some(value.clone())
+    }
+}
@@ -1,2 +1,225 @@
-pub mod part1;
-pub mod part2;
+pub mod count;
+pub mod rules;
+pub mod transitions;
+
+use anyhow::{Context, Result};
+use rustc_hash::FxHashMap;
+
+pub use count::Count;
+pub use rules::{Predicate, Rules, Transform};
+pub use transitions::Transitions;
+
+fn parse(input: &str) -> Result<Vec<u64>> {
+    input
+        .split_whitespace()
+        .map(|n| {
+            n.parse::<u64>()
+                .context(format!("failed to parse {n} as a number"))
+        })
+        .collect()
+}
+
+// How many stones have each value. The order of the stones never matters for the count and
+// there are only a few thousand different values however many stones there are, so one blink
+// is one step per value.
+pub struct Stones<C: Count = u128> {
+    counts: FxHashMap<u64, C>,
+    rules: Rules,
+    // set once a blink fails, the iterator stops there
+    failed: bool,
+}
+
+impl<C: Count> Stones<C> {
+    pub fn new(input: &str) -> Result<Stones<C>> {
+        Stones::with_rules(input, Rules::default())
+    }
+
+    pub fn with_rules(input: &str, rules: Rules) -> Result<Stones<C>> {
+        rules.validate()?;
+        let mut stones = Stones {
+            counts: FxHashMap::default(),
+            rules,
+            failed: false,
+        };
+        for stone in parse(input)? {
+            add(&mut stones.counts, stone, &C::one())?;
+        }
+        Ok(stones)
+    }
+
+    // the stones are only replaced once the whole blink worked, on an error they stay as they were
+    pub fn blink(&mut self) -> Result<()> {
+        let mut counts = FxHashMap::default();
+        for (stone, count) in &self.counts {
+            for next in self.rules.apply(*stone)? {
+                add(&mut counts, next, count)?;
+            }
+        }
+        self.counts = counts;
+        Ok(())
+    }
+
+    // (value, how many), by value
+    pub fn histogram(&self) -> Vec<(u64, C)> {
+        let mut histogram: Vec<(u64, C)> = self
+            .counts
+            .iter()
+            .map(|(stone, count)| (*stone, count.clone()))
+            .collect();
+        histogram.sort_by_key(|(stone, _)| *stone);
+        histogram
+    }
+
+    pub fn total(&self) -> Result<C> {
+        self.counts
+            .values()
+            .try_fold(C::zero(), |total, count| total.checked_add(count))
+            .context("too many stones to count")
+    }
+}
+
+fn add<C: Count>(counts: &mut FxHashMap<u64, C>, stone: u64, count: &C) -> Result<()> {
+    let entry = counts.entry(stone).or_insert_with(C::zero);
+    *entry = entry
+        .checked_add(count)
+        .with_context(|| format!("too many stones with {stone} on them"))?;
+    Ok(())
+}
+
+// the histogram after every blink, ending after the first error
+impl<C: Count> Iterator for Stones<C> {
+    type Item = Result<Vec<(u64, C)>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.blink().map(|_| self.histogram());
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+pub fn stones_after<C: Count>(input: &str, blinks: u64) -> Result<C> {
+    stones_after_with(input, &Rules::default(), blinks)
+}
+
+pub fn stones_after_with<C: Count>(input: &str, rules: &Rules, blinks: u64) -> Result<C> {
+    let mut stones = Stones::<C>::with_rules(input, rules.clone())?;
+    for _ in 0..blinks {
+        stones.blink()?;
+    }
+    stones.total()
+}
+
+// how many stones modulo a prime, for as many blinks as fit in a u64, where the exact counts
+// from `stones_after` take one step per blink
+pub fn stones_after_mod(input: &str, blinks: u64, modulus: u32) -> Result<u64> {
+    Transitions::new(input)?.count_mod(blinks, modulus)
+}
+
+pub fn part1(input: &str) -> Result<u128> {
+    stones_after(input, 25)
+}
+
+pub fn part2(input: &str) -> Result<u128> {
+    stones_after(input, 75)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::*;
+
+    #[test]
+    fn blink_once() {
+        let input = "0 1 10 99 999";
+        let result = stones_after::<u128>(input, 1);
+        assert_eq!(result.unwrap(), 7);
+    }
+
+    #[test]
+    fn blink_twice() {
+        let input = "125 17";
+        let result = stones_after::<u128>(input, 2);
+        assert_eq!(result.unwrap(), 4);
+    }
+
+    #[test]
+    fn test_part1() {
+        let input = "125 17";
+        let result = part1(input);
+        assert_eq!(result.unwrap(), 55312);
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = "125 17";
+        let result = part2(input);
+        assert_eq!(result.unwrap(), 65601038650482);
+    }
+
+    #[test]
+    fn test_histograms() {
+        let mut stones = Stones::<u128>::new("125 17").unwrap();
+        let histogram = stones.next().unwrap().unwrap();
+        assert_eq!(histogram, [(1, 1), (7, 1), (253000, 1)]);
+        let histogram = stones.nth(3).unwrap().unwrap();
+        // 1036288 7 2 20 24 4048 1 4048 8096 28 67 60 32, with 4048 twice
+        assert_eq!(histogram.len(), 12);
+        assert!(histogram.contains(&(4048, 2)));
+        assert_eq!(stones.total().unwrap(), 13);
+        stones.next().unwrap().unwrap();
+        assert_eq!(stones.total().unwrap(), 22);
+    }
+
+    #[test]
+    fn test_many_blinks() {
+        assert!(stones_after::<u128>("125 17", 300).is_err());
+        let big = stones_after::<BigUint>("125 17", 300).unwrap();
+        assert!(big > BigUint::from(u128::MAX));
+        let small = stones_after::<BigUint>("125 17", 75).unwrap();
+        assert_eq!(small, BigUint::from(65601038650482_u64));
+    }
+
+    #[test]
+    fn test_other_rules() {
+        // doubling every stone that isn't split, in base 2 stones split into 4 parts
+        let rules = Rules::new(2)
+            .rule(Predicate::DigitsMultipleOf(4), Transform::Split(4))
+            .rule(Predicate::Always, Transform::Multiply(3));
+        let mut stones = Stones::<u128>::with_rules("15 1", rules.clone()).unwrap();
+        stones.blink().unwrap();
+        assert_eq!(stones.histogram(), [(1, 4), (3, 1)]);
+        let transitions = Transitions::with_rules("15 1", &rules).unwrap();
+        let expected = stones_after_with::<u128>("15 1", &rules, 40).unwrap();
+        assert_eq!(transitions.count::<u128>(40).unwrap(), expected);
+
+        // nothing ever splits, so the stones grow until they don't fit
+        let growing = Rules::new(10).rule(Predicate::Always, Transform::Multiply(2024));
+        assert!(stones_after_with::<u128>("1", &growing, 10).is_err());
+        assert!(Transitions::with_rules("1", &growing).is_err());
+    }
+
+    #[test]
+    fn test_failed_blink() {
+        // 0 and 1 both turn into 2, too many of them to count in a u128
+        let rules = Rules::new(10)
+            .rule(Predicate::Equals(0), Transform::Replace(2))
+            .rule(Predicate::Always, Transform::Multiply(2));
+        let mut stones = Stones::<u128>::with_rules("1 1 1", rules).unwrap();
+        stones.counts.insert(0, u128::MAX);
+        let before = stones.histogram();
+        assert!(stones.blink().is_err());
+        assert_eq!(stones.histogram(), before);
+        assert!(stones.next().unwrap().is_err());
+        assert!(stones.next().is_none());
+        assert_eq!(stones.histogram(), before);
+    }
+
+    #[test]
+    fn test_bad_input() {
+        assert!(Stones::<u128>::new("12 x").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::{bail, ensure, Context, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    Equals(u64),
+    // the number of digits in the rules' base is a multiple of this
+    DigitsMultipleOf(u32),
+    Always,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transform {
+    Replace(u64),
+    // cut the digits into this many equally long stones, most significant first
+    Split(u32),
+    Multiply(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub when: Predicate,
+    pub then: Transform,
+}
+
+// The first rule that matches a stone decides what it turns into, a stone no rule matches stays
+// as it is. Nothing wraps around, a stone that doesn't fit in a u64 any more is an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    base: u64,
+    rules: Vec<Rule>,
+}
+
+impl Default for Rules {
+    // the puzzle's
+    fn default() -> Self {
+        Rules::new(10)
+            .rule(Predicate::Equals(0), Transform::Replace(1))
+            .rule(Predicate::DigitsMultipleOf(2), Transform::Split(2))
+            .rule(Predicate::Always, Transform::Multiply(2024))
+    }
+}
+
+impl Rules {
+    pub fn new(base: u64) -> Rules {
+        Rules {
+            base,
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn rule(mut self, when: Predicate, then: Transform) -> Self {
+        self.rules.push(Rule { when, then });
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.base >= 2, "base {} has to be at least 2", self.base);
+        for rule in &self.rules {
+            if let Predicate::DigitsMultipleOf(0) = rule.when {
+                bail!("digit counts can't be a multiple of 0");
+            }
+            if let Transform::Split(0) = rule.then {
+                bail!("stones can't be split into 0 parts");
+            }
+        }
+        Ok(())
+    }
+
+    fn digits(&self, stone: u64) -> u32 {
+        let mut digits = 1;
+        let mut rest = stone / self.base;
+        while rest > 0 {
+            digits += 1;
+            rest /= self.base;
+        }
+        digits
+    }
+
+    fn matches(&self, predicate: &Predicate, stone: u64) -> bool {
+        match predicate {
+            Predicate::Equals(value) => stone == *value,
+            Predicate::DigitsMultipleOf(count) => self.digits(stone).is_multiple_of(*count),
+            Predicate::Always => true,
+        }
+    }
+
+    // what one stone turns into when you blink
+    pub fn apply(&self, stone: u64) -> Result<Vec<u64>> {
+        let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| self.matches(&rule.when, stone))
+        else {
+            return Ok(vec![stone]);
+        };
+        match rule.then {
+            Transform::Replace(value) => Ok(vec![value]),
+            Transform::Multiply(factor) => match stone.checked_mul(factor) {
+                Some(stone) => Ok(vec![stone]),
+                None => bail!("stone {stone} * {factor} doesn't fit in a u64"),
+            },
+            Transform::Split(parts) => {
+                let digits = self.digits(stone);
+                ensure!(
+                    digits.is_multiple_of(parts),
+                    "stone {stone} can't be split into {parts} parts of equal length"
+                );
+                // a part too wide for a u64 is caught by the checked power
+                let part = self
+                    .base
+                    .checked_pow(digits / parts)
+                    .context("split is too wide")?;
+                let mut stones = vec![0; parts as usize];
+                let mut rest = stone;
+                for slot in stones.iter_mut().rev() {
+                    *slot = rest % part;
+                    rest /= part;
+                }
+                Ok(stones)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_puzzle_rules() {
+        let rules = Rules::default();
+        assert_eq!(rules.apply(0).unwrap(), [1]);
+        assert_eq!(rules.apply(1000).unwrap(), [10, 0]);
+        assert_eq!(rules.apply(999).unwrap(), [2021976]);
+        assert!(rules.apply(u64::MAX / 1000).is_err());
+    }
+
+    #[test]
+    fn test_configured_rules() {
+        let thirds = Rules::new(10)
+            .rule(Predicate::DigitsMultipleOf(3), Transform::Split(3))
+            .rule(Predicate::Always, Transform::Multiply(7));
+        assert_eq!(thirds.apply(123456).unwrap(), [12, 34, 56]);
+        assert_eq!(thirds.apply(100200).unwrap(), [10, 2, 0]);
+        assert_eq!(thirds.apply(12).unwrap(), [84]);
+
+        let binary = Rules::new(2).rule(Predicate::DigitsMultipleOf(2), Transform::Split(2));
+        assert_eq!(binary.apply(0b1011).unwrap(), [0b10, 0b11]);
+        // no rule for odd lengths, so they stay
+        assert_eq!(binary.apply(0b101).unwrap(), [0b101]);
+
+        let uneven = Rules::new(10).rule(Predicate::Always, Transform::Split(2));
+        assert!(uneven.apply(123).is_err());
+        assert!(Rules::new(1).validate().is_err());
+        assert!(Rules::new(10)
+            .rule(Predicate::Always, Transform::Split(0))
+            .validate()
+            .is_err());
+    }
+}
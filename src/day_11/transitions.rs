@@ -0,0 +1,441 @@
+use anyhow::{bail, ensure, Context, Result};
+use num_bigint::{BigInt, BigUint};
+use rustc_hash::FxHashMap;
+
+use super::{parse, Count, Rules};
+
+// rules that never bring stones back to values seen before could go on for a long time
+const MAX_VALUES: usize = 1_000_000;
+
+// Every value that can ever be on a stone starting from the input, and the sparse transition
+// matrix between them: each row has as many entries as the rule for that value produces stones,
+// however many values there are.
+pub struct Transitions {
+    values: Vec<u64>,
+    // indices of what the stone at every index turns into, a value split into two equal halves
+    // is in there twice
+    next: Vec<Vec<usize>>,
+    // index of every stone in the input
+    start: Vec<usize>,
+}
+
+impl Transitions {
+    pub fn new(input: &str) -> Result<Transitions> {
+        Transitions::with_rules(input, &Rules::default())
+    }
+
+    pub fn with_rules(input: &str, rules: &Rules) -> Result<Transitions> {
+        rules.validate()?;
+        let mut index: FxHashMap<u64, usize> = FxHashMap::default();
+        let mut values = Vec::new();
+        let mut find = |value: u64, values: &mut Vec<u64>| {
+            *index.entry(value).or_insert_with(|| {
+                values.push(value);
+                values.len() - 1
+            })
+        };
+        let start: Vec<usize> = parse(input)?
+            .into_iter()
+            .map(|stone| find(stone, &mut values))
+            .collect();
+        // the values get numbered in the order they are found, so this walks the closure
+        let mut next = Vec::new();
+        while next.len() < values.len() {
+            ensure!(
+                values.len() <= MAX_VALUES,
+                "more than {MAX_VALUES} different values can be on the stones"
+            );
+            let stones = rules.apply(values[next.len()])?;
+            next.push(
+                stones
+                    .into_iter()
+                    .map(|stone| find(stone, &mut values))
+                    .collect(),
+            );
+        }
+        Ok(Transitions {
+            values,
+            next,
+            start,
+        })
+    }
+
+    // every value reachable from the input, in the order they were found
+    pub fn values(&self) -> &[u64] {
+        &self.values
+    }
+
+    // one multiplication by the matrix, row vector on the left
+    fn step<T: Clone>(
+        &self,
+        counts: &[T],
+        zero: &T,
+        add: impl Fn(&T, &T) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut next = vec![zero.clone(); counts.len()];
+        for (from, count) in counts.iter().enumerate() {
+            for &to in &self.next[from] {
+                next[to] = add(&next[to], count)?;
+            }
+        }
+        Ok(next)
+    }
+
+    // the total after each of the first `terms` blinks, from none on
+    fn totals<T: Clone>(
+        &self,
+        terms: usize,
+        zero: &T,
+        one: &T,
+        add: impl Fn(&T, &T) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut counts = vec![zero.clone(); self.values.len()];
+        for &stone in &self.start {
+            counts[stone] = add(&counts[stone], one)?;
+        }
+        let mut totals = Vec::with_capacity(terms);
+        while totals.len() < terms {
+            totals.push(
+                counts
+                    .iter()
+                    .try_fold(zero.clone(), |total, count| add(&total, count))?,
+            );
+            if totals.len() < terms {
+                counts = self.step(&counts, zero, &add)?;
+            }
+        }
+        Ok(totals)
+    }
+
+    // Exact. Up to twice the number of values it takes one step of the matrix per blink, past
+    // that the total after N blinks is x^N modulo the recurrence over the integers, raised by
+    // squaring like in `count_mod`.
+    pub fn count<C: Count>(&self, blinks: u64) -> Result<C> {
+        let add = |a: &C, b: &C| a.checked_add(b).context("too many stones to count");
+        if blinks < 2 * self.values.len() as u64 {
+            let totals = self.totals(blinks as usize + 1, &C::zero(), &C::one(), add)?;
+            return Ok(totals[blinks as usize].clone());
+        }
+
+        let recurrence = self.integer_recurrence()?;
+        let totals = self.totals(recurrence.len(), &C::zero(), &C::one(), add)?;
+        let power = x_to_the_exact(blinks, &recurrence);
+        let total: BigInt = power
+            .iter()
+            .zip(&totals)
+            .map(|(coefficient, term)| coefficient * BigInt::from(term.to_biguint()))
+            .sum();
+        total
+            .to_biguint()
+            .as_ref()
+            .and_then(C::from_biguint)
+            .context("too many stones to count")
+    }
+
+    // The shortest recurrence the totals follow over the integers. Its coefficients are put
+    // together from the recurrences modulo more and more primes until one more prime doesn't
+    // change them. Primes where the recurrence comes out shorter than over the integers are
+    // skipped.
+    fn integer_recurrence(&self) -> Result<Vec<BigInt>> {
+        let mut residues: Vec<BigUint> = Vec::new();
+        let mut product = BigUint::ZERO;
+        let mut previous: Option<Vec<BigInt>> = None;
+        for prime in (2..=u32::MAX).rev().filter(|number| is_prime(*number)) {
+            let modulus = prime as u64;
+            let add = |a: &u64, b: &u64| Ok((a + b) % modulus);
+            let totals = self.totals(2 * self.values.len(), &0, &1, add)?;
+            let recurrence = berlekamp_massey(&totals, modulus);
+            if product == BigUint::ZERO || recurrence.len() > residues.len() {
+                residues = recurrence.into_iter().map(BigUint::from).collect();
+                product = BigUint::from(modulus);
+                previous = None;
+                continue;
+            }
+            if recurrence.len() < residues.len() {
+                continue;
+            }
+            // Chinese remainder theorem, one coefficient at a time
+            let inverse = pow_mod((&product % modulus).try_into()?, modulus - 2, modulus);
+            for (residue, coefficient) in residues.iter_mut().zip(recurrence) {
+                let current: u64 = (&*residue % modulus).try_into()?;
+                let lift = (coefficient + modulus - current) % modulus * inverse % modulus;
+                *residue += &product * lift;
+            }
+            product *= modulus;
+
+            // coefficients can be negative, so the residues are taken around zero
+            let half = &product >> 1;
+            let lifted: Vec<BigInt> = residues
+                .iter()
+                .map(|residue| {
+                    if *residue > half {
+                        BigInt::from(residue.clone()) - BigInt::from(product.clone())
+                    } else {
+                        BigInt::from(residue.clone())
+                    }
+                })
+                .collect();
+            if previous.as_ref() == Some(&lifted) {
+                return Ok(lifted);
+            }
+            previous = Some(lifted);
+        }
+        bail!("ran out of primes to find the recurrence over the integers")
+    }
+
+    // Modulo a prime, for any number of blinks. The totals follow a linear recurrence no longer
+    // than the number of values, which Berlekamp-Massey finds from twice that many totals, and
+    // the total after N blinks is then x^N modulo the recurrence, raised by squaring.
+    pub fn count_mod(&self, blinks: u64, modulus: u32) -> Result<u64> {
+        ensure!(is_prime(modulus), "{modulus} is not a prime");
+        let modulus = modulus as u64;
+        let add = |a: &u64, b: &u64| Ok((a + b) % modulus);
+        let terms = (2 * self.values.len() as u64).min(blinks + 1);
+        let totals = self.totals(terms as usize, &0, &1, add)?;
+        if let Some(&total) = totals.get(blinks as usize) {
+            return Ok(total);
+        }
+
+        let recurrence = berlekamp_massey(&totals, modulus);
+        let power = x_to_the(blinks, &recurrence, modulus);
+        Ok(power
+            .iter()
+            .zip(&totals)
+            .fold(0, |total, (coefficient, term)| {
+                (total + coefficient * term) % modulus
+            }))
+    }
+}
+
+fn is_prime(number: u32) -> bool {
+    number >= 2
+        && (2..)
+            .take_while(|d| *d <= number / d)
+            .all(|d| !number.is_multiple_of(d))
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    base %= modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    result
+}
+
+// c with s[n] = c[0] s[n-1] + c[1] s[n-2] + ..., the shortest one that fits all of `terms`
+fn berlekamp_massey(terms: &[u64], modulus: u64) -> Vec<u64> {
+    let mut current = vec![1_u64];
+    let mut previous = vec![1_u64];
+    let mut length = 0;
+    let mut shift = 1;
+    let mut last_discrepancy = 1;
+    for n in 0..terms.len() {
+        let discrepancy = (0..=length).fold(0, |sum, i| {
+            (sum + current.get(i).unwrap_or(&0) * terms[n - i]) % modulus
+        });
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let factor = discrepancy * pow_mod(last_discrepancy, modulus - 2, modulus) % modulus;
+        let before = current.clone();
+        if current.len() < previous.len() + shift {
+            current.resize(previous.len() + shift, 0);
+        }
+        for (i, coefficient) in previous.iter().enumerate() {
+            current[i + shift] =
+                (current[i + shift] + modulus - factor * coefficient % modulus) % modulus;
+        }
+        if 2 * length <= n {
+            length = n + 1 - length;
+            previous = before;
+            last_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+    current.resize(length + 1, 0);
+    current[1..]
+        .iter()
+        .map(|coefficient| (modulus - coefficient) % modulus)
+        .collect()
+}
+
+// x^n modulo x^L - c[0] x^(L-1) - ... - c[L-1], as its L coefficients lowest first. Sums of
+// products of numbers below 2^32 are kept in u128 and only reduced once per coefficient.
+fn x_to_the(n: u64, recurrence: &[u64], modulus: u64) -> Vec<u64> {
+    let order = recurrence.len();
+    let mut result = vec![0; order.max(1)];
+    if order == 0 {
+        return result;
+    }
+    // folds everything from x^L up back into the lower coefficients, top down
+    let reduce = |mut wide: Vec<u128>| -> Vec<u64> {
+        for i in (order..wide.len()).rev() {
+            let top = (wide[i] % modulus as u128) as u64;
+            for (j, coefficient) in recurrence.iter().enumerate() {
+                wide[i - 1 - j] += (top * coefficient) as u128;
+            }
+        }
+        wide[..order]
+            .iter()
+            .map(|coefficient| (coefficient % modulus as u128) as u64)
+            .collect()
+    };
+    result[0] = 1 % modulus;
+    for bit in (0..64 - n.leading_zeros()).rev() {
+        let mut square = vec![0_u128; 2 * order];
+        for (i, a) in result.iter().enumerate() {
+            if *a == 0 {
+                continue;
+            }
+            for (j, b) in result.iter().enumerate() {
+                square[i + j] += (a * b) as u128;
+            }
+        }
+        result = reduce(square);
+        if n >> bit & 1 == 1 {
+            let mut shifted = vec![0_u128; order + 1];
+            for (i, coefficient) in result.iter().enumerate() {
+                shifted[i + 1] = *coefficient as u128;
+            }
+            result = reduce(shifted);
+        }
+    }
+    result
+}
+
+// `x_to_the` over the integers
+fn x_to_the_exact(n: u64, recurrence: &[BigInt]) -> Vec<BigInt> {
+    let order = recurrence.len();
+    let mut result = vec![BigInt::ZERO; order.max(1)];
+    if order == 0 {
+        return result;
+    }
+    let reduce = |mut wide: Vec<BigInt>| -> Vec<BigInt> {
+        for i in (order..wide.len()).rev() {
+            let top = std::mem::take(&mut wide[i]);
+            for (j, coefficient) in recurrence.iter().enumerate() {
+                wide[i - 1 - j] += &top * coefficient;
+            }
+        }
+        wide.truncate(order);
+        wide
+    };
+    result[0] = BigInt::from(1);
+    for bit in (0..64 - n.leading_zeros()).rev() {
+        let mut square = vec![BigInt::ZERO; 2 * order];
+        for (i, a) in result.iter().enumerate() {
+            if *a == BigInt::ZERO {
+                continue;
+            }
+            for (j, b) in result.iter().enumerate() {
+                square[i + j] += a * b;
+            }
+        }
+        result = reduce(square);
+        if n >> bit & 1 == 1 {
+            result.insert(0, BigInt::ZERO);
+            result = reduce(result);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::*;
+
+    const PRIME: u32 = 1_000_000_007;
+
+    #[test]
+    fn test_closure() {
+        let transitions = Transitions::new("0").unwrap();
+        let mut values = transitions.values().to_vec();
+        values.sort();
+        assert_eq!(values.len(), 54);
+        assert_eq!(values[..12], [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 20, 24]);
+        assert_eq!(Transitions::new("125 17").unwrap().values().len(), 76);
+    }
+
+    #[test]
+    fn test_count() {
+        let transitions = Transitions::new("125 17").unwrap();
+        assert_eq!(transitions.count::<u128>(6).unwrap(), 22);
+        assert_eq!(transitions.count::<u128>(75).unwrap(), 65601038650482);
+        let big = transitions.count::<BigUint>(75).unwrap();
+        assert_eq!(big, BigUint::from(65601038650482_u64));
+
+        // past the totals that are counted one blink at a time
+        let blinks = 2 * transitions.values().len() as u64 + 37;
+        assert_eq!(
+            transitions.count::<BigUint>(blinks).unwrap(),
+            super::super::stones_after::<BigUint>("125 17", blinks).unwrap()
+        );
+        let big = transitions.count::<BigUint>(5000).unwrap();
+        assert_eq!(
+            big % PRIME,
+            BigUint::from(transitions.count_mod(5000, PRIME).unwrap())
+        );
+        assert!(transitions.count::<u128>(5000).is_err());
+    }
+
+    #[test]
+    fn test_count_mod() {
+        let transitions = Transitions::new("125 17").unwrap();
+        assert_eq!(transitions.count_mod(25, PRIME).unwrap(), 55312);
+        assert_eq!(
+            transitions.count_mod(75, PRIME).unwrap(),
+            (65601038650482 % PRIME as u64)
+        );
+        // past the totals the recurrence was found from
+        let blinks = 2 * transitions.values().len() as u64 + 37;
+        let exact = transitions.count::<BigUint>(blinks).unwrap();
+        assert_eq!(
+            BigUint::from(transitions.count_mod(blinks, PRIME).unwrap()),
+            exact % PRIME
+        );
+        assert!(transitions
+            .count_mod(1_000_000_000_000_000_000, PRIME)
+            .is_ok());
+        assert!(transitions.count_mod(10, 1_000_000_008).is_err());
+    }
+
+    #[test]
+    fn test_largest_prime() {
+        // past 65535^2, where squaring the divisor would overflow a u32
+        let prime = 4_294_967_291;
+        assert!(is_prime(prime));
+        assert!(!is_prime(u32::MAX));
+        assert_eq!(
+            super::super::stones_after_mod("125 17", 25, prime).unwrap(),
+            55312
+        );
+        let transitions = Transitions::new("125 17").unwrap();
+        let blinks = 2 * transitions.values().len() as u64 + 37;
+        let exact = transitions.count::<BigUint>(blinks).unwrap();
+        assert_eq!(
+            BigUint::from(transitions.count_mod(blinks, prime).unwrap()),
+            exact % prime
+        );
+    }
+
+    #[test]
+    fn test_berlekamp_massey() {
+        // fibonacci
+        let terms = [1, 1, 2, 3, 5, 8, 13, 21];
+        assert_eq!(berlekamp_massey(&terms, 101), [1, 1]);
+        // x^10 with x^2 = x + 1 is 55 x + 34
+        assert_eq!(x_to_the(10, &[1, 1], 101), [34, 55]);
+        let fibonacci = [BigInt::from(1), BigInt::from(1)];
+        assert_eq!(x_to_the_exact(10, &fibonacci), [34.into(), 55.into()]);
+    }
+}
@@ -1,7 +1,39 @@
-use aoc2024::day_04;
+use std::{env, io};
+
+use aoc2024::day_04::{
+    self,
+    pattern::{find_pattern, Pattern, Symmetry},
+    render::{render_to, render_to_file, RenderStyle},
+    search::{find_words, SearchOptions},
+};
 
 fn main() {
-    let input = include_str!("../day_01/input.txt");
+    let input = include_str!("../day_04/input.txt");
     println!("Part 1: {}", day_04::part1(input));
     println!("Part 2: {}", day_04::part2(input));
+
+    // `d04 render [file]` shows what was matched, coloured on the terminal or plain in a file
+    let mut args = env::args().skip(1);
+    if args.next().as_deref() != Some("render") {
+        return;
+    }
+    let puzzle = day_04::parse_wordsearch(input);
+    let xmas = find_words(&puzzle, &["XMAS"], &SearchOptions::all()).unwrap();
+    let x_mas = find_pattern(
+        &puzzle,
+        &Pattern::parse("M.S/.A./M.S").unwrap(),
+        Symmetry::Rotations,
+    );
+    match args.next() {
+        Some(path) => {
+            render_to_file(format!("{path}.xmas"), &puzzle, &xmas, RenderStyle::Plain).unwrap();
+            render_to_file(format!("{path}.x-mas"), &puzzle, &x_mas, RenderStyle::Plain).unwrap();
+        }
+        None => {
+            let mut stdout = io::stdout();
+            render_to(&mut stdout, &puzzle, &xmas, RenderStyle::Coloured).unwrap();
+            println!();
+            render_to(&mut stdout, &puzzle, &x_mas, RenderStyle::Coloured).unwrap();
+        }
+    }
 }
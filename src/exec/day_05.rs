@@ -1,7 +1,26 @@
+use std::env;
+
 use aoc2024::day_05;
 
 fn main() {
     let input = include_str!("../day_05/input.txt");
     println!("Part 1: {}", day_05::part1(input).unwrap());
     println!("Part 2: {}", day_05::part2(input).unwrap());
+
+    match env::args().nth(1).as_deref() {
+        // why every rejected update is wrong and how to fix it
+        Some("explain") => {
+            for explanation in day_05::explain_unsafe(input).unwrap() {
+                print!("{explanation}");
+            }
+        }
+        // how many orders the rules allow for each update, for updates of at most 128 pages
+        Some("count") => {
+            for (manual, count) in day_05::count_orders(input).unwrap() {
+                let pages: Vec<String> = manual.iter().map(|page| page.to_string()).collect();
+                println!("{}: {count}", pages.join(","));
+            }
+        }
+        _ => {}
+    }
 }
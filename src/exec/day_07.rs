@@ -1,7 +1,34 @@
-use aoc2024::day_07;
+use std::env;
+
+use aoc2024::day_07::{self, Solver};
 
 fn main() {
     let input = include_str!("../day_07/input.txt");
+
+    // `d07 big` solves both parts with arbitrary precision for targets that don't fit an i64,
+    // `d07 show [all]` prints the solved equations, with every way to solve them when asked
+    let mut args = env::args().skip(1);
+    let command = args.next();
+    if command.as_deref() == Some("big") {
+        println!("Part 1: {}", day_07::part1_big(input).unwrap());
+        println!("Part 2: {}", day_07::part2_big(input).unwrap());
+        return;
+    }
     println!("Part 1: {}", day_07::part1(input).unwrap());
     println!("Part 2: {}", day_07::part2(input).unwrap());
+    if command.as_deref() != Some("show") {
+        return;
+    }
+    let solver = Solver::<i64>::part2();
+    if args.next().as_deref() == Some("all") {
+        for witnesses in day_07::all_witnesses(input, &solver).unwrap() {
+            for witness in witnesses {
+                println!("{}: {}", witness.expected, witness);
+            }
+        }
+    } else {
+        for witness in day_07::solved_equations(input, &solver).unwrap() {
+            println!("{}: {}", witness.expected, witness);
+        }
+    }
 }
@@ -1,7 +1,26 @@
-use aoc2024::day_08;
+use std::env;
+
+use aoc2024::day_08::{self, Rule};
 
 fn main() {
     let input = include_str!("../day_08/input.txt");
-    println!("Part 1: {}", day_08::part1::main(input).unwrap());
-    println!("Part 2: {}", day_08::part2::main(input).unwrap());
+    println!("Part 1: {}", day_08::part1(input).unwrap());
+    println!("Part 2: {}", day_08::part2(input).unwrap());
+
+    // `d08 report` breaks both parts down per frequency and draws the antinodes
+    if env::args().nth(1).as_deref() != Some("report") {
+        return;
+    }
+    for rule in [Rule::part1(), Rule::part2()] {
+        let report = day_08::antinodes(input, &rule).unwrap();
+        println!();
+        for (frequency, count) in &report.per_frequency {
+            println!("{frequency}: {count}");
+        }
+        println!("shared by several frequencies: {}", report.overlap);
+        for ((first, second), count) in &report.shared {
+            println!("  {first} and {second}: {count}");
+        }
+        println!("{}", day_08::render(input, &rule).unwrap());
+    }
 }
@@ -1,7 +1,31 @@
+use std::env;
+
 use aoc2024::day_09;
 
 fn main() {
     let input = include_str!("../day_09/input.txt");
-    println!("Part 1: {}", day_09::part1::main(input).unwrap());
-    println!("Part 2: {}", day_09::part2::main(input).unwrap());
+    println!("Part 1: {}", day_09::part1(input).unwrap());
+    println!("Part 2: {}", day_09::part2(input).unwrap());
+
+    // `d09 compare` runs every strategy on the same disk map
+    if env::args().nth(1).as_deref() != Some("compare") {
+        return;
+    }
+    println!();
+    for (unit, strategy, report) in day_09::compare(input).unwrap() {
+        println!(
+            concat!(
+                "{:<6}{:<10}checksum {:<16}moved {:<6}",
+                "free spans {} -> {:<6}largest {:<6}fragmentation {:.3}"
+            ),
+            format!("{unit:?}"),
+            format!("{strategy:?}"),
+            report.checksum,
+            report.moved_files,
+            report.free_spans_before,
+            report.free_spans_after,
+            report.largest_free_span,
+            report.fragmentation,
+        );
+    }
 }
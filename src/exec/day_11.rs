@@ -1,7 +1,21 @@
+use std::env;
+
 use aoc2024::day_11;
 
 fn main() {
     let input = include_str!("../day_11/input-mati.txt");
-    println!("Part 1: {}", day_11::part1::main(input).unwrap());
-    println!("Part 2: {}", day_11::part2::main(input).unwrap());
+    // `d11 mod <blinks> [prime]` counts the stones after any number of blinks modulo a prime,
+    // up to 10^18; the exact counts of both parts take time linear in the number of blinks
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("mod") {
+        let blinks: u64 = args.get(1).map_or(75, |blinks| blinks.parse().unwrap());
+        let prime: u32 = args
+            .get(2)
+            .map_or(1_000_000_007, |prime| prime.parse().unwrap());
+        let count = day_11::stones_after_mod(input, blinks, prime).unwrap();
+        println!("{blinks} blinks: {count} (mod {prime})");
+        return;
+    }
+    println!("Part 1: {}", day_11::part1(input).unwrap());
+    println!("Part 2: {}", day_11::part2(input).unwrap());
 }